@@ -1,22 +1,82 @@
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read};
 use std::process::{Child, Command, Stdio};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
-use crate::parser::LogEntry;
+use crate::parser::{LineParser, LogBuffer, LogEntry, ParsedLine};
+use crate::process::is_package_name;
 
 pub enum AdbMessage {
     Entry(LogEntry),
-    UnparsedLine,
-    Disconnected(String),
+    /// The reader for this serial (None = default device or a replayed file) ended.
+    Disconnected(Option<String>, String),
+    /// `adb wait-for-device` returned for this serial (None = default device),
+    /// or why it failed.
+    DeviceReady(Option<String>, Result<(), String>),
+    Devices(Vec<Device>),
+    /// Processes seen while looking up a package's: PID and name.
+    PackageProcesses(String, Vec<(u32, String)>),
+    /// UIDs `pm` reports for a package, from a background lookup.
+    PackageUids(String, Vec<u32>),
+    /// Third-party packages installed on a device, for the package prompt.
+    Packages(Vec<String>),
+    /// A `ps -A` snapshot for this serial (None = default device): PID and name.
+    Processes(Option<String>, Vec<(u32, String)>),
+    /// Thread names for this serial: tid and `comm`.
+    Threads(Option<String>, Vec<(u32, String)>),
+}
+
+/// One row of `adb devices -l`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub serial: String,
+    pub state: String,
+    pub model: Option<String>,
+    pub transport_id: Option<String>,
+}
+
+impl Device {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace();
+        let serial = tokens.next()?.to_string();
+
+        let mut state = Vec::new();
+        let mut model = None;
+        let mut transport_id = None;
+        for token in tokens {
+            match token.split_once(':') {
+                Some(("model", value)) => model = Some(value.to_string()),
+                Some(("transport_id", value)) => transport_id = Some(value.to_string()),
+                Some(("product" | "device" | "usb", _)) => {}
+                // States like "no permissions (...)" span several words
+                _ => state.push(token),
+            }
+        }
+
+        Some(Device {
+            serial,
+            state: state.join(" "),
+            model,
+            transport_id,
+        })
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.state == "device"
+    }
 }
 
 pub struct AdbHandle {
     child: Option<Child>,
+    stopped: Arc<AtomicBool>,
 }
 
 impl AdbHandle {
     pub fn kill(&mut self) {
+        // Silence the reader thread first so a restarted stream never sees stale lines.
+        self.stopped.store(true, Ordering::Relaxed);
         if let Some(ref mut child) = self.child {
             let _ = child.kill();
             let _ = child.wait();
@@ -42,79 +102,423 @@ fn hide_window(cmd: &mut Command) -> &mut Command {
     cmd
 }
 
-pub fn spawn_logcat(tx: mpsc::Sender<AdbMessage>) -> Result<AdbHandle, String> {
+/// `adb` command targeting `serial`, or the only attached device when `None`.
+fn adb_command(serial: Option<&str>) -> Command {
     let mut cmd = Command::new("adb");
-    cmd.args(["logcat", "-v", "threadtime"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+    if let Some(serial) = serial {
+        cmd.args(["-s", serial]);
+    }
     hide_window(&mut cmd);
+    cmd
+}
+
+/// Stream `adb logcat`. With `since` (a threadtime timestamp) only entries
+/// from that time onwards are printed, so a resumed session does not replay
+/// the whole device buffer. An empty `buffers` reads the device's defaults.
+/// `uid` adds the uid column (`-v uid`).
+pub fn spawn_logcat(
+    serial: Option<&str>,
+    buffers: &[LogBuffer],
+    since: Option<&str>,
+    uid: bool,
+    tx: mpsc::Sender<AdbMessage>,
+) -> Result<AdbHandle, String> {
+    let mut cmd = adb_command(serial);
+    cmd.args(["logcat", "-v", "threadtime"]);
+    if uid {
+        cmd.args(["-v", "uid"]);
+    }
+    // -D prints a divider on every buffer switch, which is how entries get tagged
+    cmd.arg("-D");
+    if !buffers.is_empty() {
+        cmd.args(["-b", &LogBuffer::list_to_string(buffers)]);
+    }
+    if let Some(since) = since {
+        cmd.args(["-T", since]);
+    }
+    cmd.stdout(Stdio::piped())
+        .stderr(Stdio::piped());
 
     let mut child = cmd
         .spawn()
         .map_err(|e| format!("Failed to spawn adb: {}", e))?;
 
     let stdout = child.stdout.take().ok_or("Failed to capture adb stdout")?;
+    let stderr = child.stderr.take();
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let reader_stopped = Arc::clone(&stopped);
+    let serial = serial.map(str::to_string);
+    thread::spawn(move || {
+        let device = serial.as_deref().map(Arc::from);
+        let parser = LineParser::divided();
+        let reason = match forward_lines(BufReader::new(stdout), parser, &tx, device, &reader_stopped)
+        {
+            // Say why logcat quit, e.g. "more than one device/emulator"
+            Ok(()) => stderr
+                .and_then(last_error_line)
+                .unwrap_or_else(|| "ADB process ended".to_string()),
+            Err(e) => format!("ADB read error: {}", e),
+        };
+        if !reader_stopped.load(Ordering::Relaxed) {
+            let _ = tx.send(AdbMessage::Disconnected(serial, reason));
+        }
+    });
+
+    Ok(AdbHandle {
+        child: Some(child),
+        stopped,
+    })
+}
+
+/// Parse every line from `reader`, tag it with `device` and forward it to the app.
+/// Returns `Ok(())` on EOF, once `stopped` is set, or when the receiving side has gone away.
+pub fn forward_lines(
+    mut reader: impl BufRead,
+    mut parser: LineParser,
+    tx: &mpsc::Sender<AdbMessage>,
+    device: Option<Arc<str>>,
+    stopped: &AtomicBool,
+) -> io::Result<()> {
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        // Saved logs are not guaranteed to be valid UTF-8; keep going rather than abort.
+        let line = String::from_utf8_lossy(&buf);
+        // Strip trailing \r (Windows ADB outputs \r\n)
+        let line = line.trim_end_matches(['\n', '\r']);
+        let msg = match parser.feed(line) {
+            ParsedLine::Entry(mut entry) => {
+                entry.device = device.clone();
+                AdbMessage::Entry(entry)
+            }
+            ParsedLine::Structural => continue,
+        };
+        if stopped.load(Ordering::Relaxed) || tx.send(msg).is_err() {
+            return Ok(());
+        }
+    }
+}
 
+/// The last non-empty line adb wrote to stderr, once it has exited.
+fn last_error_line(mut stderr: impl Read) -> Option<String> {
+    let mut text = String::new();
+    stderr.read_to_string(&mut text).ok()?;
+    text.lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// After `delay`, block in `adb wait-for-device` on a background thread, then
+/// report back.
+pub fn spawn_wait_for_device(
+    serial: Option<String>,
+    delay: Duration,
+    tx: mpsc::Sender<AdbMessage>,
+) {
     thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
-                    // Strip trailing \r (Windows ADB outputs \r\n)
-                    let line = line.trim_end_matches('\r').to_string();
-                    let msg = if let Some(entry) = LogEntry::parse(&line) {
-                        AdbMessage::Entry(entry)
-                    } else {
-                        AdbMessage::UnparsedLine
-                    };
-                    if tx.send(msg).is_err() {
+        // Back off so a device that immediately drops again cannot spin the loop
+        thread::sleep(delay);
+        let mut cmd = adb_command(serial.as_deref());
+        cmd.arg("wait-for-device")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        let result = match cmd.output() {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(last_error_line(output.stderr.as_slice())
+                .unwrap_or_else(|| format!("adb wait-for-device failed ({})", output.status))),
+            Err(e) => Err(format!("Failed to run adb wait-for-device: {}", e)),
+        };
+        let _ = tx.send(AdbMessage::DeviceReady(serial, result));
+    });
+}
+
+pub fn list_devices() -> Result<Vec<Device>, String> {
+    let mut cmd = adb_command(None);
+    cmd.args(["devices", "-l"]);
+
+    let out = cmd
+        .output()
+        .map_err(|e| format!("Failed to run adb devices: {}", e))?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    Ok(parse_device_list(text.lines().skip(1)))
+}
+
+fn parse_device_list<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<Device> {
+    lines
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(Device::parse)
+        .collect()
+}
+
+/// Keeps the device list current via `adb track-devices -l`, falling back to
+/// polling `adb devices -l` when the adb server does not support tracking.
+pub struct DeviceTracker {
+    child: Arc<Mutex<Option<Child>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl DeviceTracker {
+    pub fn spawn(tx: mpsc::Sender<AdbMessage>) -> Self {
+        let child = Arc::new(Mutex::new(None));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread_child = Arc::clone(&child);
+        let thread_stopped = Arc::clone(&stopped);
+        thread::spawn(move || {
+            while !thread_stopped.load(Ordering::Relaxed) {
+                let tracked = track_devices(&thread_child, &tx, &thread_stopped);
+                if thread_stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                if !tracked {
+                    let devices = list_devices().unwrap_or_default();
+                    if tx.send(AdbMessage::Devices(devices)).is_err() {
                         break;
                     }
                 }
-                Err(e) => {
-                    let _ = tx.send(AdbMessage::Disconnected(format!("ADB read error: {}", e)));
-                    break;
-                }
+                thread::sleep(Duration::from_secs(2));
+            }
+        });
+
+        Self { child, stopped }
+    }
+}
+
+impl Drop for DeviceTracker {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Ok(mut child) = self.child.lock() {
+            if let Some(mut child) = child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
             }
         }
-        let _ = tx.send(AdbMessage::Disconnected("ADB process ended".to_string()));
+    }
+}
+
+/// Run one `adb track-devices -l` session until it ends.
+/// Returns whether at least one device list was received.
+fn track_devices(
+    slot: &Mutex<Option<Child>>,
+    tx: &mpsc::Sender<AdbMessage>,
+    stopped: &AtomicBool,
+) -> bool {
+    let mut cmd = adb_command(None);
+    cmd.args(["track-devices", "-l"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    let Ok(mut child) = cmd.spawn() else {
+        return false;
+    };
+    let Some(mut stdout) = child.stdout.take() else {
+        return false;
+    };
+    if let Ok(mut slot) = slot.lock() {
+        *slot = Some(child);
+    }
+
+    // Each update is a 4-digit hex length followed by `adb devices -l` rows.
+    let mut received = false;
+    let mut len_buf = [0u8; 4];
+    while stdout.read_exact(&mut len_buf).is_ok() {
+        let Some(len) = std::str::from_utf8(&len_buf)
+            .ok()
+            .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+        else {
+            break;
+        };
+        let mut payload = vec![0u8; len];
+        if stdout.read_exact(&mut payload).is_err() {
+            break;
+        }
+
+        received = true;
+        let text = String::from_utf8_lossy(&payload);
+        let devices = parse_device_list(text.lines());
+        if stopped.load(Ordering::Relaxed) || tx.send(AdbMessage::Devices(devices)).is_err() {
+            break;
+        }
+    }
+
+    if let Ok(mut slot) = slot.lock() {
+        if let Some(mut child) = slot.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+    received
+}
+
+/// Look up a package's processes on a background thread, then report back.
+pub fn spawn_package_pid_lookup(
+    serial: Option<String>,
+    package: String,
+    tx: mpsc::Sender<AdbMessage>,
+) {
+    thread::spawn(move || {
+        let processes = get_package_processes(serial.as_deref(), &package);
+        let _ = tx.send(AdbMessage::PackageProcesses(package, processes));
     });
+}
 
-    Ok(AdbHandle { child: Some(child) })
+/// Look up a package's uids on a background thread, then report back.
+pub fn spawn_package_uid_lookup(
+    serial: Option<String>,
+    package: String,
+    tx: mpsc::Sender<AdbMessage>,
+) {
+    thread::spawn(move || {
+        let uids = get_package_uids(serial.as_deref(), &package);
+        let _ = tx.send(AdbMessage::PackageUids(package, uids));
+    });
 }
 
-pub fn list_devices() -> Vec<String> {
-    let mut cmd = Command::new("adb");
-    cmd.args(["devices", "-l"]);
-    hide_window(&mut cmd);
+/// UIDs from `pm list packages -U`, which prints `package:<name> uid:<uid>`
+/// (comma-separated when the package is installed for several users).
+pub fn get_package_uids(serial: Option<&str>, package: &str) -> Vec<u32> {
+    if !is_package_name(package) {
+        return Vec::new();
+    }
+    let mut cmd = adb_command(serial);
+    cmd.args(["shell", "pm", "list", "packages", "-U", package]);
+    let Ok(output) = cmd.output() else {
+        return Vec::new();
+    };
+    // `pm` matches substrings, so com.example.app also lists com.example.app.debug
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("package:")?;
+            let (name, uids) = rest.split_once(" uid:")?;
+            (name == package).then_some(uids)
+        })
+        .flat_map(|uids| uids.split(',').filter_map(|uid| uid.trim().parse().ok()))
+        .collect()
+}
+
+/// List the device's third-party packages on a background thread, then
+/// report back.
+pub fn spawn_package_list(serial: Option<String>, tx: mpsc::Sender<AdbMessage>) {
+    thread::spawn(move || {
+        let packages = list_packages(serial.as_deref());
+        let _ = tx.send(AdbMessage::Packages(packages));
+    });
+}
 
-    match cmd.output() {
-        Ok(out) => {
-            let text = String::from_utf8_lossy(&out.stdout);
-            text.lines()
-                .skip(1)
-                .filter(|l| !l.trim().is_empty())
-                .map(|l| l.to_string())
+/// Names of the installed third-party packages, from `pm list packages -3`.
+pub fn list_packages(serial: Option<&str>) -> Vec<String> {
+    let mut cmd = adb_command(serial);
+    cmd.args(["shell", "pm list packages -3"]);
+    cmd.output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("package:"))
+                .filter_map(|rest| rest.split_whitespace().next())
+                .map(str::to_string)
                 .collect()
-        }
-        Err(_) => vec!["Failed to run adb devices".to_string()],
-    }
+        })
+        .unwrap_or_default()
 }
 
-pub fn get_package_pid(package: &str) -> Option<u32> {
-    let mut cmd = Command::new("adb");
-    cmd.args(["shell", &format!("pidof {}", package)]);
-    hide_window(&mut cmd);
+/// List the device's processes on a background thread, then report back.
+pub fn spawn_process_list(serial: Option<String>, tx: mpsc::Sender<AdbMessage>) {
+    thread::spawn(move || {
+        let processes = list_processes(serial.as_deref());
+        let _ = tx.send(AdbMessage::Processes(serial, processes));
+    });
+}
 
-    let output = cmd.output().ok()?;
-    let text = String::from_utf8_lossy(&output.stdout);
-    text.trim().split_whitespace().next()?.parse().ok()
+/// PID and name of every process, from `ps -A -o PID,NAME`. Empty where the
+/// device's `ps` does not support those options (before Android 8).
+pub fn list_processes(serial: Option<&str>) -> Vec<(u32, String)> {
+    let mut cmd = adb_command(serial);
+    cmd.args(["shell", "ps -A -o PID,NAME"]);
+    cmd.output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| {
+                    let mut columns = line.split_whitespace();
+                    let pid = columns.next()?.parse().ok()?;
+                    Some((pid, columns.next()?.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
-pub fn clear_buffer() -> Result<(), String> {
-    let mut cmd = Command::new("adb");
+/// Read the thread names of `pids` on a background thread, then report back.
+pub fn spawn_thread_list(serial: Option<String>, pids: Vec<u32>, tx: mpsc::Sender<AdbMessage>) {
+    thread::spawn(move || {
+        let threads = list_threads(serial.as_deref(), &pids);
+        let _ = tx.send(AdbMessage::Threads(serial, threads));
+    });
+}
+
+/// Tid and name of every thread of `pids`, from `/proc/<pid>/task/*/comm`,
+/// in one shell round trip.
+pub fn list_threads(serial: Option<&str>, pids: &[u32]) -> Vec<(u32, String)> {
+    let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+    let script = format!(
+        "for t in {}; do echo \"${{t##*/}} $(cat $t/comm 2>/dev/null)\"; done",
+        pids.iter()
+            .map(|pid| format!("/proc/{}/task/*", pid))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let mut cmd = adb_command(serial);
+    cmd.args(["shell", &script]);
+    cmd.output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| {
+                    // Thread names may contain spaces ("OkHttp Dispatcher")
+                    let (tid, name) = line.split_once(' ')?;
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return None;
+                    }
+                    Some((tid.parse().ok()?, name.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Every running process, for the package's main process and `:subprocesses`
+/// and for PIDs another process has taken over. Falls back to `pidof` (the
+/// package's main process only) where `ps -A -o` is not supported.
+pub fn get_package_processes(serial: Option<&str>, package: &str) -> Vec<(u32, String)> {
+    if !is_package_name(package) {
+        return Vec::new();
+    }
+    let processes = list_processes(serial);
+    if !processes.is_empty() {
+        return processes;
+    }
+
+    let mut cmd = adb_command(serial);
+    cmd.args(["shell", "pidof", package]);
+    cmd.output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .filter_map(|pid| Some((pid.parse().ok()?, package.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn clear_buffer(serial: Option<&str>) -> Result<(), String> {
+    let mut cmd = adb_command(serial);
     cmd.args(["logcat", "-c"]);
-    hide_window(&mut cmd);
 
     cmd.output()
         .map_err(|e| format!("Failed to clear logcat: {}", e))?;
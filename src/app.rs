@@ -1,22 +1,80 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
-use crate::adb;
+use regex::Regex;
+
+use crate::adb::{self, AdbHandle, AdbMessage, Device, DeviceTracker};
+use crate::cli::{Args, LogSource};
 use crate::clipboard;
+use crate::config::Config;
 use crate::export;
-use crate::filter::{is_crash_entry, FilterSet};
-use crate::parser::{LogEntry, LogLevel};
+use crate::filter::{is_crash_entry, tag_regex, FilterSet};
+use crate::line_edit::LineEditor;
+use crate::merge::StreamMerger;
+use crate::parser::{EntryKind, LogBuffer, LogEntry, LogLevel};
+use crate::preset::{self, Preset};
+use crate::process::{belongs_to_package, is_package_name, started_process, ProcessTable};
+use crate::query::{compile_regex, MatchOptions, Query};
+use crate::replay;
+use crate::timestamp::{parse_duration, TimePoint, TimeValue, Timestamp};
 
 const DEFAULT_MAX_LOG_ENTRIES: usize = 250_000;
 const MIN_MAX_LOG_ENTRIES: usize = 10_000;
 const HARD_MAX_LOG_ENTRIES: usize = 2_000_000;
+/// Entries left below a search match when jumping to it.
+const SEARCH_CONTEXT_ROWS: usize = 5;
+/// Entries kept per prompt in the saved history.
+const HISTORY_LIMIT: usize = 100;
+/// Suggestions listed under the tag and package prompts.
+const MAX_COMPLETIONS: usize = 8;
+/// A logcat that quits sooner than this after starting failed to start.
+const QUICK_EXIT: Duration = Duration::from_secs(5);
+/// Failed starts in a row before a stream stops reconnecting.
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const MAX_RECONNECT_DELAY_SECS: u64 = 30;
+/// logcat errors that waiting for the device will not fix.
+const FATAL_LOGCAT_ERRORS: [&str; 3] = ["more than one device", "unauthorized", "Unknown buffer"];
+/// Upper bound for context entries shown around filter matches.
+pub const MAX_CONTEXT: usize = 50;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
     Normal,
     Filter,
     Tag,
+    Exclude,
     Package,
+    Buffers,
+    Search,
+    Goto,
+    PresetName,
+}
+
+impl InputMode {
+    /// The prompt's key in the saved history.
+    fn history_key(self) -> Option<&'static str> {
+        match self {
+            InputMode::Normal => None,
+            InputMode::Filter => Some("filter"),
+            InputMode::Tag => Some("tag"),
+            InputMode::Exclude => Some("exclude"),
+            InputMode::Package => Some("package"),
+            InputMode::Buffers => Some("buffers"),
+            InputMode::Search => Some("search"),
+            InputMode::Goto => Some("goto"),
+            InputMode::PresetName => Some("preset"),
+        }
+    }
+}
+
+/// Name columns shown next to the PID/TID, cycled with `P`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameColumns {
+    Off,
+    Process,
+    ProcessAndThread,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,12 +84,46 @@ pub enum PanelLayout {
     SplitDevice,
 }
 
+/// Entries held back as possible leading context for the next match, and
+/// how many more may still follow the last match as trailing context.
+#[derive(Debug, Default)]
+struct ContextWindow {
+    before: VecDeque<usize>,
+    after: usize,
+    gap: bool, // entries were dropped since the last one shown
+}
+
 #[derive(Debug, Default)]
 pub struct LogStats {
-    pub counts: [usize; 6],
+    pub counts: [usize; 7],
     pub errors: usize,
 }
 
+/// One `adb logcat` reader. Several run side by side when merging devices.
+struct LiveStream {
+    serial: Option<String>,
+    handle: Option<AdbHandle>,
+    waiting: bool,
+    // Lines a resumed logcat will print again, by timestamp and message: the
+    // raw text can differ if the format changed (`-v uid`)
+    resume_skip: Vec<(Timestamp, String)>,
+    started: Option<Instant>, // when the current logcat was started
+    failures: u32,            // logcats in a row that quit right after starting
+}
+
+impl LiveStream {
+    fn new(serial: Option<String>) -> Self {
+        Self {
+            serial,
+            handle: None,
+            waiting: false,
+            resume_skip: Vec::new(),
+            started: None,
+            failures: 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LogSelection {
     pub anchor: usize,
@@ -44,55 +136,584 @@ pub struct App {
     pub crash_indices: Vec<usize>,    // absolute indices
     pub filters: FilterSet,
     pub input_mode: InputMode,
-    pub filter_input: String,
+    pub filter_input: LineEditor,
+    pub filter_error: Option<String>, // why the last query was rejected
+    pub completions: Vec<String>,     // suggestions for the prompt text, best first
+    pub completion_selected: Option<usize>, // suggestion put in the prompt with Tab
     pub tailing: bool,
     pub scroll_offset: usize,
     pub selection: Option<LogSelection>,
     pub mouse_selecting: bool,
+    pub mouse_position: Option<(u16, u16)>, // last column/row the mouse moved to
+    pub hovered: Option<usize>, // absolute index of the log line under the mouse
     pub panels: PanelLayout,
     pub show_help: bool,
-    pub device_list: Vec<String>,
+    pub devices: Vec<Device>,
+    pub device_cursor: usize,
+    pub device_focus: bool,
+    pub presets: Vec<Preset>, // shared presets first, then the user's
+    pub show_presets: bool,
+    pub preset_cursor: usize,
     pub package_filter: Option<String>,
     pub stats: LogStats,
     pub status_message: Option<String>,
     pub should_quit: bool,
+    pub source: LogSource,
+    pub serial: Option<String>, // primary device, used for PID lookups
+    pub seen_devices: Vec<Arc<str>>, // order devices first appeared, for gutter colors
+    pub buffers: Vec<LogBuffer>,     // empty = device default buffers
+    pub uid_format: bool,            // logcat runs with -v uid
+    pub expand_all: bool,
+    pub name_columns: NameColumns,
+    pub processes: ProcessTable,
+    pub context: usize, // entries shown around tag/query matches, like grep -C
+    pub search: Option<Regex>,
+    pub search_current: Option<usize>, // absolute index of the match jumped to
+    search_matches: Vec<usize>,        // absolute indices of shown entries matching `search`
+    expanded: HashSet<usize>, // absolute indices of groups toggled against expand_all
+    context_indices: HashSet<usize>, // shown entries that are only context
+    hunk_starts: HashSet<usize>,     // shown entries with a gap before them
+    context_window: ContextWindow,
+    config: Config,
+    history_position: Option<usize>, // history entry shown in the prompt, if browsing
+    history_draft: String,           // what was typed before browsing history
+    completion_candidates: Vec<String>, // everything the prompt can complete to, ranked
+    packages: Vec<String>,              // third-party packages installed on the devices
+    muted: Vec<String>, // tags muted with `m` this session, newest last, for undo
+    log_tx: mpsc::Sender<AdbMessage>,
+    streams: Vec<LiveStream>,
+    merger: StreamMerger,
+    device_tracker: Option<DeviceTracker>,
     log_base_index: usize, // absolute index of logs[0]
     max_log_entries: usize,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(args: &Args, log_tx: mpsc::Sender<AdbMessage>) -> Self {
         let max_log_entries = configured_max_log_entries();
         let initial_capacity = max_log_entries.min(100_000);
 
-        Self {
+        let mut app = Self {
             logs: VecDeque::with_capacity(initial_capacity),
             filtered_indices: Vec::new(),
             crash_indices: Vec::new(),
             filters: FilterSet::default(),
             input_mode: InputMode::Normal,
-            filter_input: String::new(),
+            filter_input: LineEditor::default(),
+            filter_error: None,
+            completions: Vec::new(),
+            completion_selected: None,
             tailing: true,
             scroll_offset: 0,
             selection: None,
             mouse_selecting: false,
+            mouse_position: None,
+            hovered: None,
             panels: PanelLayout::Single,
             show_help: false,
-            device_list: Vec::new(),
+            devices: Vec::new(),
+            device_cursor: 0,
+            device_focus: false,
+            presets: Vec::new(),
+            show_presets: false,
+            preset_cursor: 0,
             package_filter: None,
             stats: LogStats::default(),
             status_message: None,
             should_quit: false,
+            source: args.source.clone(),
+            serial: args.serials.first().cloned(),
+            seen_devices: Vec::new(),
+            buffers: args.buffers.clone(),
+            uid_format: args.uid,
+            expand_all: false,
+            name_columns: NameColumns::Off,
+            processes: ProcessTable::default(),
+            context: args.context,
+            search: None,
+            search_current: None,
+            search_matches: Vec::new(),
+            expanded: HashSet::new(),
+            context_indices: HashSet::new(),
+            hunk_starts: HashSet::new(),
+            context_window: ContextWindow::default(),
+            config: Config::default(),
+            history_position: None,
+            history_draft: String::new(),
+            completion_candidates: Vec::new(),
+            packages: Vec::new(),
+            muted: Vec::new(),
+            log_tx,
+            streams: Vec::new(),
+            merger: StreamMerger::default(),
+            device_tracker: None,
             log_base_index: 0,
             max_log_entries,
+        };
+
+        let (config, error) = Config::load();
+        let (shared, shared_error) = preset::load_project();
+        app.status_message = error.or(shared_error);
+        app.presets = shared;
+        app.apply_config(config);
+
+        if app.source.is_live() {
+            app.streams = if args.serials.is_empty() {
+                vec![LiveStream::new(None)]
+            } else {
+                args.serials.iter().cloned().map(Some).map(LiveStream::new).collect()
+            };
+            app.sync_merger();
+        }
+        app
+    }
+
+    fn apply_config(&mut self, config: Config) {
+        self.filters.exclude_tags = config.exclude_tags.clone();
+        self.filters.exclude_patterns = config
+            .exclude_patterns
+            .iter()
+            .filter_map(|p| match compile_regex(p) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    self.status_message = Some(format!("Ignoring saved exclude: {}", e));
+                    None
+                }
+            })
+            .collect();
+        self.presets.extend(config.presets.iter().cloned());
+        self.config = config;
+    }
+
+    /// Remember the exclude lists for the next run.
+    fn save_excludes(&mut self) {
+        self.config.exclude_tags = self.filters.exclude_tags.clone();
+        self.config.exclude_patterns = self
+            .filters
+            .exclude_patterns
+            .iter()
+            .map(|re| re.as_str().to_string())
+            .collect();
+        if let Err(e) = self.config.save() {
+            self.status_message = Some(format!("Could not save exclude list: {}", e));
+        }
+    }
+
+    /// The line a one-key action applies to: the selection's focus, else the
+    /// line under the mouse.
+    fn target_entry(&self) -> Option<&LogEntry> {
+        let idx = self.selection.map(|s| s.focus).or(self.hovered)?;
+        self.entry_at(idx)
+    }
+
+    fn target_tag(&mut self) -> Option<String> {
+        match self.target_entry() {
+            Some(entry) if !entry.tag.is_empty() => Some(entry.tag.clone()),
+            Some(_) => {
+                self.status_message = Some("That line has no tag".to_string());
+                None
+            }
+            None => {
+                self.status_message =
+                    Some("Point at or select a log line first".to_string());
+                None
+            }
+        }
+    }
+
+    /// Hide the target line's tag by adding it to the exclude list.
+    pub fn mute_target_tag(&mut self) {
+        let Some(tag) = self.target_tag() else {
+            return;
+        };
+        if !self.filters.exclude_tags.contains(&tag) {
+            self.filters.exclude_tags.push(tag.clone());
+            self.muted.push(tag.clone());
+            self.save_excludes();
+            self.refilter();
+        }
+        self.status_message = Some(format!("Muted {} (u: undo, T: review)", tag));
+    }
+
+    /// Show only the target line's tag, or everything again if it already is.
+    pub fn isolate_target_tag(&mut self) {
+        let Some(tag) = self.target_tag() else {
+            return;
+        };
+        let isolated = self.filters.is_exact_tag(&tag);
+        if isolated {
+            let _ = self.filters.set_tag_filter(None);
+            self.status_message = Some(format!("Stopped isolating {}", tag));
+        } else {
+            self.filters.set_exact_tag_filter(&tag);
+            self.status_message = Some(format!("Isolated {} (i again to undo)", tag));
+        }
+        self.refilter();
+    }
+
+    /// Unmute the most recently muted tag.
+    pub fn undo_mute(&mut self) {
+        let Some(tag) = self.muted.pop() else {
+            self.status_message = Some("Nothing to unmute (T edits the exclude list)".to_string());
+            return;
+        };
+        self.filters.exclude_tags.retain(|t| *t != tag);
+        self.save_excludes();
+        self.refilter();
+        self.status_message = Some(format!("Unmuted {}", tag));
+    }
+
+    /// Start the log reader: live adb, or a saved file replayed through the same channel.
+    pub fn start_stream(&mut self) -> Result<(), String> {
+        let tx = self.log_tx.clone();
+        match &self.source {
+            LogSource::Adb => {
+                for i in 0..self.streams.len() {
+                    self.spawn_logcat(i, None)?;
+                }
+            }
+            LogSource::File(path) => replay::spawn_file(path, tx)?,
+            LogSource::Stdin => replay::spawn_stdin(tx),
+        }
+        Ok(())
+    }
+
+    fn spawn_logcat(&mut self, stream: usize, since: Option<&str>) -> Result<(), String> {
+        let tx = self.log_tx.clone();
+        let stream = &mut self.streams[stream];
+        let handle =
+            adb::spawn_logcat(stream.serial.as_deref(), &self.buffers, since, self.uid_format, tx)
+                .map_err(|e| format!("ADB error: {}", e))?;
+        stream.handle = Some(handle);
+        stream.waiting = false;
+        stream.started = Some(Instant::now());
+        Ok(())
+    }
+
+    fn stop_streams(&mut self) {
+        for stream in &mut self.streams {
+            if let Some(mut handle) = stream.handle.take() {
+                handle.kill();
+            }
+        }
+        for entry in self.merger.drain() {
+            self.add_entry(entry);
+        }
+        self.streams.clear();
+    }
+
+    fn sync_merger(&mut self) {
+        let devices: Vec<Option<Arc<str>>> = self
+            .streams
+            .iter()
+            .map(|s| s.serial.as_deref().map(Arc::from))
+            .collect();
+        self.merger.set_active(&devices);
+    }
+
+    pub fn is_waiting_for_device(&self) -> bool {
+        self.streams.iter().any(|s| s.waiting)
+    }
+
+    pub fn is_streaming_from(&self, serial: &str) -> bool {
+        self.streams
+            .iter()
+            .any(|s| s.serial.as_deref() == Some(serial))
+    }
+
+    pub fn is_merging(&self) -> bool {
+        self.streams.len() > 1
+    }
+
+    /// The reader hit EOF. Live sessions keep their buffer and wait for the
+    /// device to come back, backing off while logcat keeps quitting right
+    /// away; replayed files simply report that they finished.
+    pub fn handle_disconnect(&mut self, serial: Option<String>, reason: String) {
+        let stream = self
+            .streams
+            .iter_mut()
+            .find(|s| s.serial == serial && s.handle.is_some());
+        let Some(stream) = stream else {
+            self.status_message = Some(reason);
+            return;
+        };
+
+        if let Some(mut handle) = stream.handle.take() {
+            handle.kill();
+        }
+        let device = serial.as_deref().unwrap_or("device");
+
+        // Some errors (several devices and no serial, an unsupported buffer)
+        // would only come back; a logcat that keeps quitting at once for any
+        // other reason is retried with a growing delay, a few times
+        if FATAL_LOGCAT_ERRORS.iter().any(|fatal| reason.contains(fatal)) {
+            self.status_message = Some(format!("logcat for {} failed: {}", device, reason));
+            return;
+        }
+        if stream.started.is_some_and(|t| t.elapsed() < QUICK_EXIT) {
+            stream.failures += 1;
+            if stream.failures >= MAX_RECONNECT_ATTEMPTS {
+                self.status_message =
+                    Some(format!("logcat for {} keeps failing, stopped: {}", device, reason));
+                return;
+            }
+        } else {
+            stream.failures = 0;
+        }
+
+        stream.waiting = true;
+        let delay = Duration::from_secs((1 << stream.failures).min(MAX_RECONNECT_DELAY_SECS));
+        self.status_message = Some(format!("{}, waiting for {}...", reason, device));
+        adb::spawn_wait_for_device(serial, delay, self.log_tx.clone());
+    }
+
+    /// `adb wait-for-device` returned: resume logcat where the buffer left off.
+    pub fn handle_device_ready(&mut self, serial: Option<String>, result: Result<(), String>) {
+        // Ignore waiters left over from a device the user has since switched away from
+        let Some(stream) = self
+            .streams
+            .iter()
+            .position(|s| s.waiting && s.serial == serial)
+        else {
+            return;
+        };
+        if let Err(e) = result {
+            self.streams[stream].waiting = false;
+            let device = serial.as_deref().unwrap_or("device");
+            self.status_message = Some(format!("Stopped waiting for {}: {}", device, e));
+            return;
+        }
+
+        match self.resume_stream(stream, serial.as_deref()) {
+            Ok(()) => {
+                let device = serial.as_deref().unwrap_or("device");
+                let text = format!("reconnected to {}", device);
+                self.status_message = Some(format!("Reconnected to {}", device));
+                self.add_entry(LogEntry::marker(&text));
+            }
+            Err(e) => {
+                self.streams[stream].waiting = false;
+                self.status_message = Some(e);
+            }
+        }
+    }
+
+    /// Start logcat for `stream` after the newest line already held from
+    /// `device`, skipping the lines `-T` prints again, so nothing shows twice.
+    /// With no lines from the device yet, its whole buffer is read.
+    fn resume_stream(&mut self, stream: usize, device: Option<&str>) -> Result<(), String> {
+        // Newest lines first, folded ones included
+        let recent = || {
+            self.logs
+                .iter()
+                .rev()
+                .filter(|e| e.device.as_deref() == device)
+                .flat_map(|e| e.folded.iter().rev().chain(std::iter::once(e)))
+        };
+        let since = recent()
+            .find(|e| e.kind == EntryKind::Log)
+            .map(|e| e.timestamp.clone());
+        let mut resume_skip: Vec<(Timestamp, String)> = recent()
+            .filter(|e| matches!(e.kind, EntryKind::Log | EntryKind::Continuation))
+            .take_while(|e| Some(&e.timestamp) == since.as_ref())
+            .map(|e| (e.timestamp.clone(), e.message.clone()))
+            .collect();
+        resume_skip.reverse();
+        self.streams[stream].resume_skip = resume_skip;
+        self.spawn_logcat(stream, since.as_ref().map(Timestamp::as_str))
+    }
+
+    /// Restart the live stream, PID lookups and buffer clears against `serial` alone.
+    pub fn switch_device(&mut self, serial: String) {
+        if !self.source.is_live() {
+            self.status_message =
+                Some("Device switching is only available for live adb sessions".to_string());
+            return;
+        }
+
+        // The default device by its name: tag what was read rather than read it again
+        let online: Vec<&Device> = self.devices.iter().filter(|d| d.is_online()).collect();
+        if self.streams.len() == 1
+            && self.streams[0].serial.is_none()
+            && matches!(online.as_slice(), [only] if only.serial == serial)
+        {
+            self.status_message = Some(match self.pin_default_stream(serial.clone()) {
+                Ok(()) => format!("Streaming from {}", serial),
+                Err(e) => e,
+            });
+            return;
+        }
+
+        self.stop_streams();
+        self.streams.push(LiveStream::new(Some(serial.clone())));
+        self.sync_merger();
+        self.serial = Some(serial);
+
+        // The old PIDs belong to the previous device; resolve the package again.
+        if self.package_filter.is_some() {
+            self.filters.pid_filter.clear();
+            self.refilter();
+            self.poll_package_pids();
+        }
+
+        // Switching back to a device already shown picks up where it left off
+        let device = self.serial.clone();
+        self.status_message = Some(match self.resume_stream(0, device.as_deref()) {
+            Ok(()) => format!("Streaming from {}", self.serial.as_deref().unwrap_or("")),
+            Err(e) => e,
+        });
+    }
+
+    /// Give the default-device stream its serial so merged entries can be told
+    /// apart. Lines already read are tagged too, and logcat resumes after them.
+    fn pin_default_stream(&mut self, serial: String) -> Result<(), String> {
+        for entry in self.merger.drain() {
+            self.add_entry(entry);
+        }
+        let device: Arc<str> = Arc::from(serial.as_str());
+        for entry in self.logs.iter_mut() {
+            if entry.device.is_some() || entry.kind == EntryKind::Marker {
+                continue;
+            }
+            entry.device = Some(Arc::clone(&device));
+            for folded in &mut entry.folded {
+                folded.device = Some(Arc::clone(&device));
+            }
+        }
+        if !self.seen_devices.contains(&device) {
+            self.seen_devices.push(device);
+        }
+
+        if let Some(mut handle) = self.streams[0].handle.take() {
+            handle.kill();
+        }
+        self.streams[0] = LiveStream::new(Some(serial.clone()));
+        self.serial = Some(serial.clone());
+        self.sync_merger();
+        self.resume_stream(0, Some(&serial))
+    }
+
+    /// Add `serial` to the merged view, or remove it if it is already streaming.
+    pub fn toggle_merge_device(&mut self, serial: String) {
+        if !self.source.is_live() {
+            self.status_message =
+                Some("Merging devices is only available for live adb sessions".to_string());
+            return;
+        }
+
+        if let Some(i) = self.streams.iter().position(|s| s.serial.as_deref() == Some(&serial)) {
+            if self.streams.len() == 1 {
+                self.status_message = Some(format!("{} is the only device streaming", serial));
+                return;
+            }
+            let mut stream = self.streams.remove(i);
+            if let Some(mut handle) = stream.handle.take() {
+                handle.kill();
+            }
+            self.sync_merger();
+            self.serial = self.streams[0].serial.clone();
+            self.status_message = Some(format!("Stopped merging {}", serial));
+            return;
+        }
+
+        // Merged entries need a device tag; pin the default device to its serial.
+        if self.streams.len() == 1 && self.streams[0].serial.is_none() {
+            let others: Vec<&Device> = self
+                .devices
+                .iter()
+                .filter(|d| d.is_online() && d.serial != serial)
+                .collect();
+            let [current] = others.as_slice() else {
+                self.status_message =
+                    Some("Press Enter on the current device before merging another".to_string());
+                return;
+            };
+            let current = current.serial.clone();
+            if let Err(e) = self.pin_default_stream(current) {
+                self.status_message = Some(e);
+                return;
+            }
+        }
+
+        self.streams.push(LiveStream::new(Some(serial.clone())));
+        self.sync_merger();
+        let i = self.streams.len() - 1;
+        self.status_message = Some(match self.spawn_logcat(i, None) {
+            Ok(()) => format!("Merging {} device streams", self.streams.len()),
+            Err(e) => e,
+        });
+    }
+
+    /// Route an entry from a reader: resumed-stream dedup, then timestamp merge.
+    pub fn ingest(&mut self, entry: LogEntry) {
+        let device = entry.device.as_deref();
+        if let Some(stream) = self
+            .streams
+            .iter_mut()
+            .find(|s| s.serial.as_deref() == device)
+        {
+            // A resumed logcat starts at the last timestamp we saw; drop the repeats
+            if !stream.resume_skip.is_empty() {
+                let (ref timestamp, ref message) = stream.resume_skip[0];
+                if *timestamp == entry.timestamp && *message == entry.message {
+                    stream.resume_skip.remove(0);
+                    return;
+                }
+                stream.resume_skip.clear();
+            }
+        }
+
+        if self.is_merging() {
+            self.merger.push(entry);
+            self.flush_merged();
+        } else {
+            self.add_entry(entry);
         }
     }
 
+    /// Move merged entries whose turn has come into the log.
+    pub fn flush_merged(&mut self) {
+        let now = Instant::now();
+        while let Some(entry) = self.merger.pop_ready(now) {
+            self.add_entry(entry);
+        }
+    }
+
+    /// Palette slot for a device's gutter color.
+    pub fn device_color_index(&self, device: Option<&str>) -> Option<usize> {
+        let device = device?;
+        self.seen_devices.iter().position(|d| &**d == device)
+    }
+
     pub fn add_entry(&mut self, entry: LogEntry) {
-        // Update stats
-        self.stats.counts[entry.level.index()] += 1;
-        if entry.level >= LogLevel::Error {
-            self.stats.errors += 1;
+        if let Some(ref device) = entry.device {
+            if !self.seen_devices.contains(device) {
+                self.seen_devices.push(Arc::clone(device));
+            }
+        }
+
+        // Stack frames and wrapped lines join the record they continue
+        if self.logs.back().is_some_and(|last| last.continued_by(&entry)) {
+            self.fold_into_last(entry);
+            return;
+        }
+
+        // Update stats (unparsed lines count under LogLevel::Unknown)
+        if entry.kind != EntryKind::Marker {
+            self.stats.counts[entry.level.index()] += 1;
+            if entry.level >= LogLevel::Error {
+                self.stats.errors += 1;
+            }
+        }
+
+        // A restarted app brings new PIDs into the package filter
+        if let Some((pid, name)) = started_process(&entry) {
+            self.processes.insert(entry.device.clone(), pid, name);
+        }
+        match self.package_process_start(&entry) {
+            Some((pid, true)) => self.add_package_pids(vec![pid], &entry.timestamp),
+            Some((pid, false)) => self.end_package_pid(pid, &entry.timestamp),
+            None => {}
         }
 
         let idx = self.log_base_index + self.logs.len();
@@ -102,13 +723,8 @@ impl App {
             self.crash_indices.push(idx);
         }
 
-        // Check filter
-        let matches_filter = self.filters.matches(&entry);
-        if matches_filter {
-            self.filtered_indices.push(idx);
-        }
-
         self.logs.push_back(entry);
+        let shown = self.place(idx);
 
         // Evict if over capacity
         if self.logs.len() > self.max_log_entries {
@@ -118,28 +734,396 @@ impl App {
             // Drop stale absolute indices; no shifting needed.
             self.filtered_indices.retain(|i| *i >= self.log_base_index);
             self.crash_indices.retain(|i| *i >= self.log_base_index);
+            self.search_matches.retain(|i| *i >= self.log_base_index);
+            let base = self.log_base_index;
+            self.expanded.retain(|i| *i >= base);
+            self.context_indices.retain(|i| *i >= base);
+            self.hunk_starts.retain(|i| *i >= base);
+            self.context_window.before.retain(|i| *i >= base);
+            if self.search_current.is_some_and(|i| i < base) {
+                self.search_current = None;
+            }
         }
 
         // Keep paused viewport anchored to the same entries.
         // Without this, newly appended matching logs shift a paused view forward.
-        if !self.tailing && matches_filter {
-            self.scroll_offset = self.scroll_offset.saturating_add(1);
+        if !self.tailing {
+            self.scroll_offset = self.scroll_offset.saturating_add(shown);
         }
 
         self.clear_selection_if_stale();
         self.clamp_scroll_offset();
     }
 
+    /// Append a continuation line to the newest record. The record is filtered
+    /// and flagged as a crash as a whole, so either may change now.
+    fn fold_into_last(&mut self, entry: LogEntry) {
+        if entry.kind == EntryKind::Continuation {
+            self.stats.counts[LogLevel::Unknown.index()] += 1;
+        }
+        let idx = self.log_base_index + self.logs.len() - 1;
+        let crash = is_crash_entry(&entry);
+        let Some(last) = self.logs.back_mut() else {
+            return;
+        };
+        last.folded.push(entry);
+
+        if crash && self.crash_indices.last() != Some(&idx) {
+            self.crash_indices.push(idx);
+        }
+        let matches = self.filters.matches(last, &self.processes);
+        let context_only = !matches && self.filters.matches_base(last);
+
+        if self.filtered_indices.last() == Some(&idx)
+            && !matches
+            && !(context_only && self.context_indices.contains(&idx))
+        {
+            // The new line excludes a record already shown (an exclude pattern,
+            // a negated `msg:`): lay the view out again as refilter would
+            self.refilter();
+        } else if self.filtered_indices.last() != Some(&idx) {
+            if matches {
+                if self.context_window.before.back() == Some(&idx) {
+                    self.context_window.before.pop_back();
+                }
+                let shown = self.place(idx);
+                if !self.tailing {
+                    self.scroll_offset = self.scroll_offset.saturating_add(shown);
+                }
+            }
+        } else if matches && self.context_indices.remove(&idx) {
+            // A context line that now matches starts a new run of trailing context
+            self.context_window.after = self.context;
+        }
+        let shown = self.filtered_indices.last() == Some(&idx);
+        if shown
+            && self.search_matches.last() != Some(&idx)
+            && self.entry_at(idx).is_some_and(|e| self.is_search_match(e))
+        {
+            self.search_matches.push(idx);
+        }
+    }
+
+    /// Decide whether the newest entry is shown: as a match (bringing up to
+    /// `context` held-back entries with it), as trailing context, or held back.
+    /// Returns how many entries were added to the view.
+    fn place(&mut self, idx: usize) -> usize {
+        let Some(entry) = self.entry_at(idx) else {
+            return 0;
+        };
+        if entry.kind == EntryKind::Marker {
+            self.filtered_indices.push(idx);
+            return 1;
+        }
+        if !self.filters.matches_base(entry) {
+            return 0;
+        }
+
+        if self.filters.matches_pattern(entry, &self.processes) {
+            let before: Vec<usize> = self.context_window.before.drain(..).collect();
+            let shown = before.len() + 1;
+            for i in before {
+                self.show(i, true);
+            }
+            self.show(idx, false);
+            self.context_window.after = self.context;
+            shown
+        } else if self.context_window.after > 0 {
+            self.context_window.after -= 1;
+            self.show(idx, true);
+            1
+        } else {
+            self.context_window.before.push_back(idx);
+            if self.context_window.before.len() > self.context {
+                self.context_window.before.pop_front();
+                self.context_window.gap = true;
+            }
+            0
+        }
+    }
+
+    fn show(&mut self, idx: usize, context: bool) {
+        let gap = std::mem::take(&mut self.context_window.gap);
+        if gap && self.context > 0 && !self.filtered_indices.is_empty() {
+            self.hunk_starts.insert(idx);
+        }
+        if context {
+            self.context_indices.insert(idx);
+        }
+        self.filtered_indices.push(idx);
+        if self.entry_at(idx).is_some_and(|e| self.is_search_match(e)) {
+            self.search_matches.push(idx);
+        }
+    }
+
+    /// Shown only as context around a match, not a match itself.
+    pub fn is_context(&self, absolute_idx: usize) -> bool {
+        self.context_indices.contains(&absolute_idx)
+    }
+
+    /// Whether a `--` separator precedes the entry, as between grep -C hunks.
+    pub fn starts_hunk(&self, absolute_idx: usize) -> bool {
+        self.hunk_starts.contains(&absolute_idx)
+    }
+
+    /// `[` / `]`: show fewer or more context entries around matches.
+    pub fn adjust_context(&mut self, delta: isize) {
+        self.context = self.context.saturating_add_signed(delta).min(MAX_CONTEXT);
+        self.refilter();
+        self.status_message = Some(match self.context {
+            0 => "Context off".to_string(),
+            n if self.filters.has_pattern() => format!("Context: {} entries around matches", n),
+            n => format!("Context: {} entries (applies to tag and query filters)", n),
+        });
+    }
+
+    /// Rows an entry takes in the log view: one, plus its folded lines when expanded.
+    pub fn entry_rows(&self, absolute_idx: usize) -> usize {
+        let separator = usize::from(self.starts_hunk(absolute_idx));
+        match self.entry_at(absolute_idx) {
+            Some(entry) if self.is_expanded(absolute_idx) => separator + 1 + entry.folded.len(),
+            Some(_) => separator + 1,
+            None => 0,
+        }
+    }
+
+    pub fn is_expanded(&self, absolute_idx: usize) -> bool {
+        self.expand_all != self.expanded.contains(&absolute_idx)
+    }
+
+    /// Expand or collapse the selected stack traces.
+    pub fn toggle_expand_selection(&mut self) {
+        let Some((start, end)) = self.selected_positions() else {
+            self.status_message = Some("Select a stack trace first".to_string());
+            return;
+        };
+        let groups: Vec<usize> = self.filtered_indices[start..end]
+            .iter()
+            .copied()
+            .filter(|&idx| self.entry_at(idx).is_some_and(|e| !e.folded.is_empty()))
+            .collect();
+        if groups.is_empty() {
+            self.status_message = Some("No multi-line entries selected".to_string());
+            return;
+        }
+        // Mixed selections all go the same way, following the first group
+        let expand = !self.is_expanded(groups[0]);
+        for idx in groups {
+            if expand != self.expand_all {
+                self.expanded.insert(idx);
+            } else {
+                self.expanded.remove(&idx);
+            }
+        }
+    }
+
+    pub fn toggle_expand_all(&mut self) {
+        self.expand_all = !self.expand_all;
+        self.expanded.clear();
+        self.status_message = Some(if self.expand_all {
+            "Expanded all multi-line entries".to_string()
+        } else {
+            "Collapsed all multi-line entries".to_string()
+        });
+    }
+
     pub fn refilter(&mut self) {
         self.filtered_indices.clear();
-        for (idx, entry) in self.logs.iter().enumerate() {
-            if self.filters.matches(entry) {
-                self.filtered_indices.push(self.log_base_index + idx);
+        self.context_indices.clear();
+        self.hunk_starts.clear();
+        self.context_window = ContextWindow::default();
+        for idx in self.log_base_index..self.log_base_index + self.logs.len() {
+            self.place(idx);
+        }
+        self.refresh_search_matches();
+        // Keep crash indices as-is (they don't depend on user filters)
+        self.clear_selection();
+        self.clamp_scroll_offset();
+    }
+
+    /// Whether `entry` contains the search pattern, in its tag or any line.
+    pub fn is_search_match(&self, entry: &LogEntry) -> bool {
+        self.search.as_ref().is_some_and(|re| search_hit(re, entry))
+    }
+
+    fn refresh_search_matches(&mut self) {
+        self.search_matches = match self.search {
+            Some(ref re) => self
+                .filtered_indices
+                .iter()
+                .copied()
+                .filter(|&idx| self.entry_at(idx).is_some_and(|e| search_hit(re, e)))
+                .collect(),
+            None => Vec::new(),
+        };
+        if let Some(current) = self.search_current {
+            if self.search_matches.binary_search(&current).is_err() {
+                self.search_current = None;
+            }
+        }
+    }
+
+    pub fn begin_search_input(&mut self) {
+        let text = self
+            .search
+            .as_ref()
+            .map(|re| re.as_str().to_string())
+            .unwrap_or_default();
+        self.begin_input(InputMode::Search, text);
+    }
+
+    /// Set the search pattern and jump to the newest match above the bottom of
+    /// the view. Unlike the filter, non-matching lines stay visible.
+    fn set_search(&mut self, pattern: &str) -> Result<(), String> {
+        self.search = if pattern.is_empty() {
+            None
+        } else {
+            Some(compile_regex(pattern)?)
+        };
+        self.search_current = None;
+        self.refresh_search_matches();
+        if self.search.is_some() {
+            self.jump_to_match(false);
+        }
+        Ok(())
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = None;
+        self.search_current = None;
+        self.search_matches.clear();
+    }
+
+    /// (1-based position of the current match, total matches) for the status bar.
+    pub fn search_position(&self) -> Option<(Option<usize>, usize)> {
+        self.search.as_ref()?;
+        let current = self
+            .search_current
+            .and_then(|idx| self.search_matches.binary_search(&idx).ok())
+            .map(|i| i + 1);
+        Some((current, self.search_matches.len()))
+    }
+
+    /// `n` / `N`: move to the next match below, or previous above, the current
+    /// one (or the bottom of the view), wrapping around at either end.
+    pub fn jump_to_match(&mut self, forward: bool) {
+        if self.search.is_none() {
+            self.status_message = Some("No search (press f to search)".to_string());
+            return;
+        }
+        if self.search_matches.is_empty() {
+            self.status_message = Some("No matches".to_string());
+            return;
+        }
+
+        let total = self.filtered_indices.len();
+        let bottom = total.saturating_sub(self.scroll_offset + 1);
+        let anchor = self
+            .search_current
+            .or_else(|| self.filtered_indices.get(bottom).copied())
+            .unwrap_or(0);
+        let matches = &self.search_matches;
+        let next = if forward {
+            let i = matches.partition_point(|&idx| idx <= anchor);
+            matches.get(i).or(matches.first())
+        } else {
+            // Without a current match, the bottom entry itself counts
+            let i = match self.search_current {
+                Some(_) => matches.partition_point(|&idx| idx < anchor),
+                None => matches.partition_point(|&idx| idx <= anchor),
+            };
+            i.checked_sub(1).and_then(|i| matches.get(i)).or(matches.last())
+        };
+        let Some(&target) = next else {
+            return;
+        };
+
+        let wrapped = self
+            .search_current
+            .is_some_and(|current| (target <= current) == forward);
+        self.status_message = wrapped.then(|| "Search wrapped".to_string());
+        self.search_current = Some(target);
+        if let Ok(position) = self.filtered_indices.binary_search(&target) {
+            self.tailing = false;
+            self.scroll_offset = (total - position - 1).saturating_sub(SEARCH_CONTEXT_ROWS);
+        }
+    }
+
+    /// Alt+C / Alt+R / Alt+W: flip smart-case, literal or whole-word matching.
+    pub fn set_match_options(&mut self, options: MatchOptions) {
+        if let Err(e) = self.filters.set_match_options(options) {
+            self.status_message = Some(format!("Can't switch: {}", e));
+            return;
+        }
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        self.status_message = Some(format!(
+            "Smart case {}, {}, whole word {}",
+            on_off(options.smart_case),
+            if options.literal { "literal" } else { "regex" },
+            on_off(options.whole_word),
+        ));
+        self.validate_input();
+        self.refilter();
+    }
+
+    /// Time of the newest line, the "now" of a replayed file; the clock when
+    /// there is none yet.
+    fn newest_time(&self) -> TimeValue {
+        self.logs
+            .iter()
+            .rev()
+            .filter(|e| e.kind == EntryKind::Log)
+            .find_map(|e| e.timestamp.value())
+            .unwrap_or_else(TimeValue::now)
+    }
+
+    pub fn begin_goto_input(&mut self) {
+        self.begin_input(InputMode::Goto, String::new());
+    }
+
+    /// A time, or `-30s` for that long before the newest line.
+    fn parse_goto(&self, input: &str) -> Result<TimePoint, String> {
+        match input.strip_prefix('-') {
+            Some(ago) => Ok(TimePoint::At(self.newest_time().before(parse_duration(ago)?))),
+            None => TimePoint::parse(input),
+        }
+    }
+
+    /// Scroll to the first shown line at or after `point` (the last line if
+    /// everything is earlier) and select it.
+    pub fn goto_time(&mut self, point: TimePoint) {
+        let mut comparable = false;
+        let mut target = None;
+        for (position, &idx) in self.filtered_indices.iter().enumerate() {
+            let Some(order) = self.entry_at(idx).and_then(|e| point.cmp_timestamp(&e.timestamp))
+            else {
+                continue;
+            };
+            comparable = true;
+            target = Some((position, idx));
+            if order.is_ge() {
+                break;
             }
         }
-        // Keep crash indices as-is (they don't depend on user filters)
-        self.clear_selection();
-        self.clamp_scroll_offset();
+        let Some((position, idx)) = target else {
+            self.status_message = Some(if comparable {
+                "No lines to jump to".to_string()
+            } else {
+                "No shown line has a comparable time".to_string()
+            });
+            return;
+        };
+
+        let total = self.filtered_indices.len();
+        self.tailing = false;
+        self.scroll_offset = (total - position - 1).saturating_sub(SEARCH_CONTEXT_ROWS);
+        self.selection = Some(LogSelection {
+            anchor: idx,
+            focus: idx,
+        });
+        if let Some(entry) = self.entry_at(idx) {
+            self.status_message = Some(format!("Jumped to {}", entry.timestamp));
+        }
     }
 
     pub fn scroll_up(&mut self, amount: usize) {
@@ -182,38 +1166,428 @@ impl App {
     pub fn submit_filter_input(&mut self) {
         match self.input_mode {
             InputMode::Filter => {
-                self.filters.set_regex(&self.filter_input);
+                // Keep the prompt open on a bad query so it can be fixed
+                let now = self.newest_time();
+                if let Err(e) = self.filters.set_query(&self.filter_input, now) {
+                    self.filter_error = Some(e);
+                    return;
+                }
+                self.filter_error = None;
+                if self.filters.query.as_ref().is_some_and(Query::uses_uid) {
+                    self.enable_uid_format();
+                }
                 self.refilter();
+                self.poll_processes();
             }
             InputMode::Tag => {
-                if self.filter_input.is_empty() {
-                    self.filters.tag_filter = None;
-                } else {
-                    self.filters.tag_filter = Some(self.filter_input.clone());
+                if let Err(e) = self.filters.set_tag_filter(Some(&self.filter_input)) {
+                    self.filter_error = Some(e);
+                    return;
+                }
+                self.filter_error = None;
+                self.refilter();
+            }
+            InputMode::Exclude => {
+                if let Err(e) = self.filters.set_excludes(&self.filter_input) {
+                    self.filter_error = Some(e);
+                    return;
                 }
+                self.filter_error = None;
+                self.save_excludes();
                 self.refilter();
             }
             InputMode::Package => {
-                if self.filter_input.is_empty() {
-                    self.package_filter = None;
-                    self.filters.pid_filter = None;
-                } else {
-                    let pkg = self.filter_input.clone();
-                    // Try to resolve PID immediately
-                    if let Some(pid) = adb::get_package_pid(&pkg) {
-                        self.filters.pid_filter = Some(pid);
+                if let Some(e) = self.input_error() {
+                    self.filter_error = Some(e);
+                    return;
+                }
+                let package = Some(self.filter_input.trim().to_string()).filter(|p| !p.is_empty());
+                self.set_package_filter(package);
+            }
+            InputMode::Search => {
+                let pattern = self.filter_input.trim().to_string();
+                if let Err(e) = self.set_search(&pattern) {
+                    self.filter_error = Some(e);
+                    return;
+                }
+                self.filter_error = None;
+            }
+            InputMode::Goto => {
+                match self.parse_goto(self.filter_input.trim()) {
+                    Ok(point) => self.goto_time(point),
+                    Err(e) => {
+                        self.filter_error = Some(e);
+                        return;
                     }
-                    self.package_filter = Some(pkg);
                 }
-                self.refilter();
+                self.filter_error = None;
+            }
+            InputMode::Buffers => match LogBuffer::parse_list(&self.filter_input) {
+                Ok(buffers) => self.set_buffers(buffers),
+                Err(e) => self.status_message = Some(e),
+            },
+            InputMode::PresetName => {
+                let name = self.filter_input.trim().to_string();
+                if !name.is_empty() {
+                    self.save_preset(name);
+                }
             }
             InputMode::Normal => {}
         }
+        self.remember_input();
         self.filter_input.clear();
         self.input_mode = InputMode::Normal;
     }
 
+    /// Filter to a package's processes, including ones it starts later, or
+    /// drop the package filter.
+    fn set_package_filter(&mut self, package: Option<String>) {
+        self.filters.pid_filter.clear();
+        self.filters.uid_filter.clear();
+        self.package_filter = package;
+        if self.package_filter.is_some() {
+            // Processes started within the buffer are known without asking
+            // the device, which is all a replayed file has to go on
+            let starts: Vec<(u32, bool, Timestamp)> = self
+                .logs
+                .iter()
+                .filter_map(|entry| {
+                    let (pid, ours) = self.package_process_start(entry)?;
+                    Some((pid, ours, entry.timestamp.clone()))
+                })
+                .collect();
+            for (pid, ours, at) in starts {
+                if ours {
+                    self.filters.add_package_pid(pid, &at);
+                } else {
+                    self.filters.end_package_pid(pid, &at);
+                }
+            }
+            self.poll_package_pids();
+        }
+        self.refilter();
+    }
+
+    pub fn toggle_presets(&mut self) {
+        self.show_presets = !self.show_presets;
+        self.preset_cursor = self.preset_cursor.min(self.presets.len().saturating_sub(1));
+    }
+
+    pub fn move_preset_cursor(&mut self, delta: isize) {
+        let max = self.presets.len().saturating_sub(1);
+        self.preset_cursor = self.preset_cursor.saturating_add_signed(delta).min(max);
+    }
+
+    /// Save the current filters under `name`, replacing a preset of that name.
+    fn save_preset(&mut self, name: String) {
+        let existing = self.presets.iter().position(|p| p.name == name);
+        let preset = Preset {
+            query: self.filters.query.as_ref().map(|q| q.source.clone()).unwrap_or_default(),
+            tag: self.filters.tag_filter().unwrap_or_default().to_string(),
+            package: self.package_filter.clone(),
+            level_toggles: self.filters.level_toggles,
+            match_options: self.filters.match_options,
+            shared: existing.is_some_and(|i| self.presets[i].shared),
+            name,
+        };
+        self.status_message = Some(format!("Saved preset {}", preset.name));
+        let shared = preset.shared;
+        self.preset_cursor = match existing {
+            Some(i) => {
+                self.presets[i] = preset;
+                i
+            }
+            None => {
+                self.presets.push(preset);
+                self.presets.len() - 1
+            }
+        };
+        self.store_presets(shared);
+    }
+
+    /// Replace the filters with the preset under the picker cursor. The
+    /// exclude lists stay.
+    pub fn apply_preset(&mut self) {
+        let Some(preset) = self.presets.get(self.preset_cursor).cloned() else {
+            return;
+        };
+        self.filters.reset();
+        self.filters.level_toggles = preset.level_toggles;
+        self.filters.match_options = preset.match_options;
+        let now = self.newest_time();
+        let result = self
+            .filters
+            .set_query(&preset.query, now)
+            .and_then(|()| self.filters.set_tag_filter(Some(&preset.tag)));
+        if self.filters.query.as_ref().is_some_and(Query::uses_uid) {
+            self.enable_uid_format();
+        }
+        self.set_package_filter(preset.package);
+        self.poll_processes();
+        self.show_presets = false;
+        self.status_message = Some(match result {
+            Ok(()) => format!("Applied preset {}", preset.name),
+            Err(e) => format!("Preset {}: {}", preset.name, e),
+        });
+    }
+
+    pub fn delete_preset(&mut self) {
+        if self.preset_cursor >= self.presets.len() {
+            return;
+        }
+        let preset = self.presets.remove(self.preset_cursor);
+        self.move_preset_cursor(0);
+        self.status_message = Some(format!("Deleted preset {}", preset.name));
+        self.store_presets(preset.shared);
+    }
+
+    /// Move the preset under the cursor between the user's config and the
+    /// project file, where it can be committed for the team.
+    pub fn toggle_preset_shared(&mut self) {
+        let Some(preset) = self.presets.get_mut(self.preset_cursor) else {
+            return;
+        };
+        preset.shared = !preset.shared;
+        self.status_message = Some(if preset.shared {
+            format!("Preset {} moved to {}", preset.name, preset::PROJECT_FILE)
+        } else {
+            format!("Preset {} moved to your config", preset.name)
+        });
+        // Left where it was if either file could not take it
+        if !self.store_presets(true) {
+            let preset = &mut self.presets[self.preset_cursor];
+            preset.shared = !preset.shared;
+        }
+    }
+
+    /// Write the user's presets, and first the project file when a shared
+    /// preset changed, so a preset moving between the two is never in neither.
+    /// Returns whether everything was written.
+    fn store_presets(&mut self, shared_changed: bool) -> bool {
+        let (shared, personal): (Vec<Preset>, Vec<Preset>) =
+            self.presets.iter().cloned().partition(|p| p.shared);
+        let result = if shared_changed {
+            preset::save_project(&shared)
+        } else {
+            Ok(())
+        };
+        let result = result.and_then(|()| {
+            self.config.presets = personal;
+            self.config.save()
+        });
+        if let Err(ref e) = result {
+            self.status_message = Some(format!("Could not save presets: {}", e));
+        }
+        result.is_ok()
+    }
+
+    /// Open a prompt with `text` ready to edit.
+    pub fn begin_input(&mut self, mode: InputMode, text: String) {
+        self.input_mode = mode;
+        self.filter_input.set(text);
+        self.filter_error = None;
+        self.history_position = None;
+        self.completion_candidates = match mode {
+            InputMode::Tag => self.tags_by_frequency(),
+            InputMode::Package => {
+                self.poll_packages();
+                self.package_names()
+            }
+            _ => Vec::new(),
+        };
+        self.update_completions();
+    }
+
+    /// Tags in the buffer, most frequent first.
+    fn tags_by_frequency(&self) -> Vec<String> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for entry in self.logs.iter().filter(|e| e.kind == EntryKind::Log) {
+            *counts.entry(entry.tag.as_str()).or_default() += 1;
+        }
+        let mut tags: Vec<(&str, usize)> = counts.into_iter().filter(|(tag, _)| !tag.is_empty()).collect();
+        tags.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        tags.into_iter().map(|(tag, _)| tag.to_string()).collect()
+    }
+
+    /// Installed packages, plus app processes seen in the logs, which is all
+    /// a replayed file has to go on.
+    fn package_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .processes
+            .names()
+            .filter(|name| name.contains('.') && !name.contains([':', '/']))
+            .map(str::to_string)
+            .chain(self.packages.iter().cloned())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Ask the live devices for their installed packages. Answers arrive as
+    /// `AdbMessage::Packages`.
+    fn poll_packages(&self) {
+        if !self.source.is_live() {
+            return;
+        }
+        for stream in &self.streams {
+            adb::spawn_package_list(stream.serial.clone(), self.log_tx.clone());
+        }
+    }
+
+    pub fn handle_packages(&mut self, packages: Vec<String>) {
+        self.packages.extend(packages);
+        self.packages.sort_unstable();
+        self.packages.dedup();
+        if self.input_mode == InputMode::Package {
+            self.completion_candidates = self.package_names();
+            if self.completion_selected.is_none() {
+                self.update_completions();
+            }
+        }
+    }
+
+    /// Suggest candidates for the prompt text: those starting with it, then
+    /// those containing it, ignoring case.
+    fn update_completions(&mut self) {
+        let typed = self.filter_input.trim().to_lowercase();
+        let (mut starts, contains): (Vec<&String>, Vec<&String>) = self
+            .completion_candidates
+            .iter()
+            .filter(|c| c.to_lowercase().contains(&typed) && **c != self.filter_input.trim())
+            .partition(|c| c.to_lowercase().starts_with(&typed));
+        starts.extend(contains);
+        self.completions = starts.into_iter().take(MAX_COMPLETIONS).cloned().collect();
+        self.completion_selected = None;
+    }
+
+    /// Tab (or Shift+Tab backwards): put the next suggestion in the prompt.
+    pub fn complete(&mut self, forward: bool) {
+        let count = self.completions.len();
+        if count == 0 {
+            return;
+        }
+        let next = match self.completion_selected {
+            None if forward => 0,
+            None => count - 1,
+            Some(i) if forward => (i + 1) % count,
+            Some(i) => (i + count - 1) % count,
+        };
+        self.completion_selected = Some(next);
+        let completion = &self.completions[next];
+        self.filter_input.set(completion.clone());
+        self.filter_error = self.input_error();
+    }
+
+    /// Up in a prompt: replace the text with the previous entry for it.
+    pub fn history_prev(&mut self) {
+        let len = self.prompt_history().len();
+        let position = match self.history_position {
+            Some(0) => return,
+            Some(i) => i - 1,
+            None if len == 0 => return,
+            None => {
+                self.history_draft = self.filter_input.to_string();
+                len - 1
+            }
+        };
+        let text = self.prompt_history()[position].clone();
+        self.history_position = Some(position);
+        self.filter_input.set(text);
+        self.validate_input();
+    }
+
+    /// Down in a prompt: the next entry, and past the newest the text that
+    /// was being typed.
+    pub fn history_next(&mut self) {
+        let Some(position) = self.history_position else {
+            return;
+        };
+        let text = match self.prompt_history().get(position + 1) {
+            Some(text) => {
+                let text = text.clone();
+                self.history_position = Some(position + 1);
+                text
+            }
+            None => {
+                self.history_position = None;
+                std::mem::take(&mut self.history_draft)
+            }
+        };
+        self.filter_input.set(text);
+        self.validate_input();
+    }
+
+    fn prompt_history(&self) -> &[String] {
+        self.input_mode
+            .history_key()
+            .and_then(|key| self.config.history.get(key))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Add the submitted prompt text to its history and save it for the next
+    /// run. A repeated entry moves to the end instead of appearing twice.
+    fn remember_input(&mut self) {
+        let text = self.filter_input.trim();
+        let Some(key) = self.input_mode.history_key() else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        let entries = self.config.history.entry(key.to_string()).or_default();
+        entries.retain(|entry| entry != text);
+        entries.push(text.to_string());
+        if entries.len() > HISTORY_LIMIT {
+            entries.drain(..entries.len() - HISTORY_LIMIT);
+        }
+        if let Err(e) = self.config.save() {
+            self.status_message = Some(format!("Could not save prompt history: {}", e));
+        }
+    }
+
+    /// Open the query prompt with the current query, ready to edit.
+    pub fn begin_filter_input(&mut self) {
+        let text = self
+            .filters
+            .query
+            .as_ref()
+            .map(|q| q.source.clone())
+            .unwrap_or_default();
+        self.begin_input(InputMode::Filter, text);
+    }
+
+    /// Open the exclude prompt with the current lists, ready to edit.
+    pub fn begin_exclude_input(&mut self) {
+        self.begin_input(InputMode::Exclude, self.filters.exclude_text());
+    }
+
+    /// Re-check the prompt after each edit so mistakes show while typing,
+    /// and suggest completions for the new text.
+    pub fn validate_input(&mut self) {
+        self.filter_error = self.input_error();
+        self.update_completions();
+    }
+
+    fn input_error(&self) -> Option<String> {
+        let input = self.filter_input.trim();
+        match self.input_mode {
+            InputMode::Filter if !input.is_empty() => {
+                Query::parse(input, &self.filters.match_options).err()
+            }
+            InputMode::Tag if !input.is_empty() => tag_regex(input, &self.filters.match_options).err(),
+            InputMode::Exclude => FilterSet::default().set_excludes(input).err(),
+            InputMode::Search => compile_regex(input).err().filter(|_| !input.is_empty()),
+            InputMode::Goto if !input.is_empty() => self.parse_goto(input).err(),
+            InputMode::Package if !input.is_empty() && !is_package_name(input) => {
+                Some("Not a package name (letters, digits, '.', '_', ':')".to_string())
+            }
+            _ => None,
+        }
+    }
+
     pub fn cancel_input(&mut self) {
+        self.filter_error = None;
         self.filter_input.clear();
         self.input_mode = InputMode::Normal;
     }
@@ -226,7 +1600,123 @@ impl App {
     }
 
     pub fn clear_logs(&mut self) {
-        let _ = adb::clear_buffer();
+        for stream in &self.streams {
+            let _ = adb::clear_buffer(stream.serial.as_deref());
+        }
+        self.reset_logs();
+        self.status_message = Some("Buffer cleared".to_string());
+    }
+
+    /// Open the buffer prompt pre-filled with the current selection.
+    pub fn begin_buffer_input(&mut self) {
+        if !self.source.is_live() {
+            self.status_message =
+                Some("Buffers can only be chosen for live adb sessions".to_string());
+            return;
+        }
+        self.begin_input(InputMode::Buffers, LogBuffer::list_to_string(&self.buffers));
+    }
+
+    /// Re-read every stream from the selected buffers. The device still holds
+    /// the history, so the in-memory log is rebuilt rather than duplicated.
+    pub fn set_buffers(&mut self, buffers: Vec<LogBuffer>) {
+        if buffers == self.buffers {
+            return;
+        }
+        self.buffers = buffers;
+        self.status_message = Some(match self.restart_streams() {
+            Ok(()) if self.buffers.is_empty() => "Reading default buffers".to_string(),
+            Ok(()) => format!("Reading buffers {}", LogBuffer::list_to_string(&self.buffers)),
+            Err(e) => e,
+        });
+    }
+
+    /// Switch logcat to `-v uid` so uid filters have something to match. The
+    /// lines already read are kept, without a uid; logcat resumes after them.
+    fn enable_uid_format(&mut self) {
+        if self.uid_format || !self.source.is_live() {
+            return;
+        }
+        self.uid_format = true;
+        for entry in self.merger.drain() {
+            self.add_entry(entry);
+        }
+        let mut result = Ok(());
+        for i in 0..self.streams.len() {
+            // A stream waiting for its device resumes with the column anyway
+            if self.streams[i].waiting {
+                continue;
+            }
+            if let Some(mut handle) = self.streams[i].handle.take() {
+                handle.kill();
+            }
+            let device = self.streams[i].serial.clone();
+            result = result.and(self.resume_stream(i, device.as_deref()));
+        }
+        self.add_entry(LogEntry::marker("uid column on; earlier lines have no uid"));
+        self.status_message = Some(match result {
+            Ok(()) => "Reading uids (-v uid) from here on; earlier lines have none".to_string(),
+            Err(e) => e,
+        });
+    }
+
+    fn restart_streams(&mut self) -> Result<(), String> {
+        for stream in &mut self.streams {
+            if let Some(mut handle) = stream.handle.take() {
+                handle.kill();
+            }
+            stream.waiting = false;
+            stream.resume_skip.clear();
+            stream.failures = 0;
+        }
+        self.merger.drain();
+        self.reset_logs();
+
+        let mut result = Ok(());
+        for i in 0..self.streams.len() {
+            result = result.and(self.spawn_logcat(i, None));
+        }
+        result
+    }
+
+    /// `U`: show only the uids the package filter's app runs under, or drop
+    /// that filter again.
+    pub fn toggle_package_uid_filter(&mut self) {
+        if !self.filters.uid_filter.is_empty() {
+            self.filters.uid_filter.clear();
+            self.refilter();
+            self.status_message = Some("UID filter cleared".to_string());
+            return;
+        }
+        let Some(ref pkg) = self.package_filter else {
+            self.status_message = Some("Set a package filter (p) first".to_string());
+            return;
+        };
+        if !self.source.is_live() {
+            self.status_message =
+                Some("Looking up a package's uid needs a live adb session".to_string());
+            return;
+        }
+        adb::spawn_package_uid_lookup(self.serial.clone(), pkg.clone(), self.log_tx.clone());
+        self.status_message = Some(format!("Looking up uid of {}...", pkg));
+    }
+
+    pub fn handle_package_uids(&mut self, package: &str, uids: Vec<u32>) {
+        if self.package_filter.as_deref() != Some(package) {
+            return;
+        }
+        if uids.is_empty() {
+            self.status_message = Some(format!("pm lists no uid for {}", package));
+            return;
+        }
+        let list = uids.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+        self.filters.uid_filter = uids;
+        self.enable_uid_format();
+        self.refilter();
+        self.status_message = Some(format!("Showing uid {} ({})", list, package));
+    }
+
+    fn reset_logs(&mut self) {
         self.logs.clear();
         self.filtered_indices.clear();
         self.crash_indices.clear();
@@ -234,8 +1724,13 @@ impl App {
         self.tailing = true;
         self.scroll_offset = 0;
         self.clear_selection();
+        self.expanded.clear();
+        self.context_indices.clear();
+        self.hunk_starts.clear();
+        self.context_window = ContextWindow::default();
+        self.search_matches.clear();
+        self.search_current = None;
         self.log_base_index = 0;
-        self.status_message = Some("Buffer cleared".to_string());
     }
 
     pub fn export_logs(&mut self) {
@@ -254,11 +1749,73 @@ impl App {
         }
     }
 
-    pub fn refresh_devices(&mut self) {
-        self.device_list = adb::list_devices();
+    pub fn set_devices(&mut self, devices: Vec<Device>) {
+        // Keep the cursor on the same device when the list reorders
+        let current = self.devices.get(self.device_cursor).map(|d| d.serial.clone());
+        self.devices = devices;
+        self.device_cursor = current
+            .and_then(|serial| self.devices.iter().position(|d| d.serial == serial))
+            .unwrap_or(self.device_cursor)
+            .min(self.devices.len().saturating_sub(1));
+    }
+
+    pub fn move_device_cursor(&mut self, delta: isize) {
+        let max = self.devices.len().saturating_sub(1);
+        self.device_cursor = self.device_cursor.saturating_add_signed(delta).min(max);
+    }
+
+    /// Switch the live stream to the device at `index` in the device panel.
+    pub fn select_device(&mut self, index: usize) {
+        let Some(device) = self.devices.get(index) else {
+            return;
+        };
+        self.device_cursor = index;
+
+        if !device.is_online() {
+            self.status_message = Some(format!("{} is {}", device.serial, device.state));
+        } else if self.serial.as_deref() != Some(device.serial.as_str()) {
+            let serial = device.serial.clone();
+            self.switch_device(serial);
+        }
+    }
+
+    pub fn toggle_merge_cursor_device(&mut self) {
+        if let Some(device) = self.devices.get(self.device_cursor) {
+            if device.is_online() || self.is_streaming_from(&device.serial) {
+                let serial = device.serial.clone();
+                self.toggle_merge_device(serial);
+            } else {
+                self.status_message = Some(format!("{} is {}", device.serial, device.state));
+            }
+        }
+    }
+
+    /// Show only the device under the cursor, or every device again.
+    pub fn toggle_device_filter(&mut self) {
+        let Some(device) = self.devices.get(self.device_cursor) else {
+            return;
+        };
+        if self.filters.device_filter.as_deref() == Some(device.serial.as_str()) {
+            self.filters.device_filter = None;
+        } else {
+            // The default stream's lines carry no serial; filtering on one
+            // would hide them all
+            let tagged = self.seen_devices.iter().any(|d| **d == *device.serial)
+                || self.streams.iter().any(|s| s.serial.as_ref() == Some(&device.serial));
+            if !tagged {
+                self.status_message = Some(format!(
+                    "Lines are not tagged with {} (m merges it into the view first)",
+                    device.serial
+                ));
+                return;
+            }
+            self.filters.device_filter = Some(device.serial.clone());
+        }
+        self.refilter();
     }
 
     pub fn toggle_crash_panel(&mut self) {
+        self.device_focus = false;
         self.panels = match self.panels {
             PanelLayout::SplitCrash => PanelLayout::Single,
             _ => PanelLayout::SplitCrash,
@@ -267,46 +1824,237 @@ impl App {
 
     pub fn toggle_device_panel(&mut self) {
         self.panels = match self.panels {
-            PanelLayout::SplitDevice => PanelLayout::Single,
+            PanelLayout::SplitDevice => {
+                self.device_focus = false;
+                PanelLayout::Single
+            }
             _ => {
-                self.refresh_devices();
+                // Tracking starts on first use and then keeps the list live
+                if self.device_tracker.is_none() {
+                    self.device_tracker = Some(DeviceTracker::spawn(self.log_tx.clone()));
+                }
+                // Opens unfocused so `m`, `f` and the arrows keep their log
+                // view meaning until Tab
                 PanelLayout::SplitDevice
             }
         };
     }
 
-    /// Try to resolve package PID if we have a package filter but no PID yet
-    pub fn poll_package_pid(&mut self) {
+    /// Ask the device which processes the package is running. The answer
+    /// arrives as `AdbMessage::PackageProcesses`.
+    pub fn poll_package_pids(&mut self) {
+        if !self.source.is_live() {
+            return;
+        }
         if let Some(ref pkg) = self.package_filter {
-            if self.filters.pid_filter.is_none() {
-                if let Some(pid) = adb::get_package_pid(pkg) {
-                    self.filters.pid_filter = Some(pid);
-                    self.status_message = Some(format!("Found PID {} for {}", pid, pkg));
-                    self.refilter();
-                }
+            adb::spawn_package_pid_lookup(self.serial.clone(), pkg.clone(), self.log_tx.clone());
+        }
+    }
+
+    pub fn handle_package_processes(&mut self, package: &str, processes: Vec<(u32, String)>) {
+        // Drop answers for a package filter that has since changed
+        if self.package_filter.as_deref() != Some(package) {
+            return;
+        }
+        let (ours, others): (Vec<_>, Vec<_>) = processes
+            .into_iter()
+            .partition(|(_, name)| belongs_to_package(name, package));
+        // PIDs changed hands at some point since the last look; the newest
+        // line is as close to when as can be told
+        let newest = self
+            .logs
+            .iter()
+            .rev()
+            .find(|e| e.kind == EntryKind::Log)
+            .map_or_else(Timestamp::now, |e| e.timestamp.clone());
+        for (pid, _) in others {
+            self.end_package_pid(pid, &newest);
+        }
+        self.add_package_pids(ours.into_iter().map(|(pid, _)| pid).collect(), &newest);
+    }
+
+    /// Refresh process names from every live device. Answers arrive as
+    /// `AdbMessage::Processes`.
+    pub fn poll_processes(&self) {
+        if !self.source.is_live() || !self.needs_process_names() {
+            return;
+        }
+        for stream in &self.streams {
+            adb::spawn_process_list(stream.serial.clone(), self.log_tx.clone());
+        }
+    }
+
+    /// Process names are only worth `ps -A` for the name columns and
+    /// `proc:` queries.
+    fn needs_process_names(&self) -> bool {
+        self.name_columns != NameColumns::Off
+            || self.filters.query.as_ref().is_some_and(Query::uses_process_names)
+    }
+
+    pub fn handle_processes(&mut self, serial: Option<String>, processes: Vec<(u32, String)>) {
+        let device: Option<Arc<str>> = serial.map(Arc::from);
+        let mut changed = false;
+        for (pid, name) in processes {
+            changed |= self.processes.insert(device.clone(), pid, &name);
+        }
+        if changed && self.filters.query.as_ref().is_some_and(Query::uses_process_names) {
+            self.refilter();
+        }
+    }
+
+    /// Resolve thread names for the processes on screen. Answers arrive as
+    /// `AdbMessage::Threads`.
+    pub fn poll_threads(&self, height: usize) {
+        if self.name_columns != NameColumns::ProcessAndThread || !self.source.is_live() {
+            return;
+        }
+        let (start, end, _) = self.visible_window(height);
+        for stream in &self.streams {
+            let device = stream.serial.as_deref();
+            let mut pids: Vec<u32> = self.filtered_indices[start..end]
+                .iter()
+                .filter_map(|&idx| self.entry_at(idx))
+                .filter(|e| e.kind == EntryKind::Log && e.pid != 0 && e.device.as_deref() == device)
+                .map(|e| e.pid)
+                .collect();
+            pids.sort_unstable();
+            pids.dedup();
+            if !pids.is_empty() {
+                adb::spawn_thread_list(stream.serial.clone(), pids, self.log_tx.clone());
             }
         }
     }
 
-    pub fn visible_bounds(&self, height: usize) -> (usize, usize) {
-        let total = self.filtered_indices.len();
+    pub fn handle_threads(&mut self, serial: Option<String>, threads: Vec<(u32, String)>) {
+        let device: Option<Arc<str>> = serial.map(Arc::from);
+        for (tid, name) in threads {
+            self.processes.insert_thread(device.clone(), tid, &name);
+        }
+    }
 
-        if self.tailing {
-            let start = total.saturating_sub(height);
-            (start, total)
+    /// `P`: no name columns, process names, then process and thread names.
+    pub fn cycle_name_columns(&mut self) {
+        let (next, message) = match self.name_columns {
+            NameColumns::Off => (NameColumns::Process, "Showing process names"),
+            NameColumns::Process => (NameColumns::ProcessAndThread, "Showing process and thread names"),
+            NameColumns::ProcessAndThread => (NameColumns::Off, "Hiding process names"),
+        };
+        self.name_columns = next;
+        self.status_message = Some(message.to_string());
+        self.poll_processes();
+    }
+
+    /// Show only the thread that wrote the entry, or every thread again if
+    /// that one already is isolated.
+    pub fn isolate_thread(&mut self, absolute_idx: usize) {
+        let Some(entry) = self.entry_at(absolute_idx) else {
+            return;
+        };
+        if !entry.format.has_tid() || entry.kind != EntryKind::Log {
+            self.status_message = Some("That line has no thread id".to_string());
+            return;
+        }
+        let tid = entry.tid;
+        let thread = (entry.device.clone(), tid);
+        let name = self
+            .processes
+            .thread_name(entry)
+            .map(|name| format!(" ({})", name))
+            .unwrap_or_default();
+        if self.filters.thread_filter.as_ref() == Some(&thread) {
+            self.filters.thread_filter = None;
+            self.status_message = Some(format!("Stopped isolating thread {}{}", tid, name));
+        } else {
+            self.filters.thread_filter = Some(thread);
+            self.status_message = Some(format!("Isolated thread {}{}", tid, name));
+        }
+        self.refilter();
+    }
+
+    pub fn isolate_target_thread(&mut self) {
+        match self.selection.map(|s| s.focus).or(self.hovered) {
+            Some(idx) => self.isolate_thread(idx),
+            None => {
+                self.status_message = Some("Point at or select a log line first".to_string());
+            }
+        }
+    }
+
+    /// A process start announced by an ActivityManager line, while a package
+    /// filter is set: the PID, and whether the process is the package's.
+    fn package_process_start(&self, entry: &LogEntry) -> Option<(u32, bool)> {
+        let package = self.package_filter.as_deref()?;
+        let (pid, process) = started_process(entry)?;
+        Some((pid, belongs_to_package(process, package)))
+    }
+
+    /// Add processes to the package filter. PIDs of processes that have died
+    /// stay, so the lines leading up to a crash remain visible after a restart,
+    /// until another process reuses them. `at` is when the processes were seen.
+    fn add_package_pids(&mut self, pids: Vec<u32>, at: &Timestamp) {
+        let first = self.filters.pid_filter.is_empty();
+        let new: Vec<u32> = pids
+            .into_iter()
+            .filter(|&pid| self.filters.add_package_pid(pid, at))
+            .collect();
+        if new.is_empty() {
+            return;
+        }
+        let pkg = self.package_filter.as_deref().unwrap_or_default();
+        let list = new.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+        self.status_message = Some(if first {
+            format!("Found PID {} for {}", list, pkg)
+        } else {
+            format!("{} started PID {}", pkg, list)
+        });
+        self.refilter();
+    }
+
+    /// A PID the package filter follows now belongs to another process: its
+    /// lines from `at` on are not the package's.
+    fn end_package_pid(&mut self, pid: u32, at: &Timestamp) {
+        if self.filters.end_package_pid(pid, at) {
+            self.status_message = Some(format!("PID {} was reused by another process", pid));
+            self.refilter();
+        }
+    }
+
+    /// Filtered positions `[start, end)` that fill `height` rows up to the
+    /// scroll position, and how many leading rows of `start` fall off the top
+    /// when an expanded entry does not fit whole.
+    pub fn visible_window(&self, height: usize) -> (usize, usize, usize) {
+        let total = self.filtered_indices.len();
+        let end = if self.tailing {
+            total
         } else {
-            let end = total.saturating_sub(self.scroll_offset);
-            let start = end.saturating_sub(height);
-            (start, end)
+            total.saturating_sub(self.scroll_offset)
+        };
+
+        let mut start = end;
+        let mut rows = 0;
+        while start > 0 && rows < height {
+            start -= 1;
+            rows += self.entry_rows(self.filtered_indices[start]);
         }
+        // A paused view scrolled onto an entry taller than the screen shows its top
+        let overflow = rows.saturating_sub(height);
+        if !self.tailing && end - start == 1 {
+            return (start, end, 0);
+        }
+        (start, end, overflow)
     }
 
     pub fn visible_entry_at_row(&self, height: usize, row: usize) -> Option<usize> {
-        let (start, end) = self.visible_bounds(height);
-        if row >= end.saturating_sub(start) {
-            return None;
+        let (start, end, skip) = self.visible_window(height);
+        let mut row = row + skip;
+        for &idx in &self.filtered_indices[start..end] {
+            let rows = self.entry_rows(idx);
+            if row < rows {
+                return Some(idx);
+            }
+            row -= rows;
         }
-        self.filtered_indices.get(start + row).copied()
+        None
     }
 
     pub fn begin_selection(&mut self, absolute_idx: usize) {
@@ -350,15 +2098,16 @@ impl App {
             return;
         };
 
-        let text = self.filtered_indices[start..end]
+        let lines: Vec<&str> = self.filtered_indices[start..end]
             .iter()
-            .filter_map(|&idx| self.entry_at(idx).map(|entry| entry.raw.as_str()))
-            .collect::<Vec<_>>()
-            .join("\n");
+            .filter_map(|&idx| self.entry_at(idx))
+            .flat_map(LogEntry::raw_lines)
+            .collect();
+        let text = lines.join("\n");
 
         match clipboard::copy_text(&text) {
             Ok(()) => {
-                let count = end - start;
+                let count = lines.len();
                 let suffix = if count == 1 { "" } else { "s" };
                 self.status_message = Some(format!("Copied {} log line{} to clipboard", count, suffix));
             }
@@ -404,6 +2153,13 @@ impl App {
     }
 }
 
+fn search_hit(re: &Regex, entry: &LogEntry) -> bool {
+    re.is_match(&entry.tag)
+        || std::iter::once(entry)
+            .chain(&entry.folded)
+            .any(|e| re.is_match(&e.message))
+}
+
 fn configured_max_log_entries() -> usize {
     env::var("COLORED_LOGCAT_MAX_ENTRIES")
         .ok()
@@ -0,0 +1,145 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::app::MAX_CONTEXT;
+use crate::parser::LogBuffer;
+
+const USAGE: &str = "\
+Usage: colored-logcat [OPTIONS]
+
+Options:
+  -f, --file <PATH>   Replay a saved logcat file instead of a live adb stream
+                      (use - to read from stdin)
+  -s, --serial <SERIAL>
+                      Stream from the device with this serial (see `adb devices`);
+                      repeat to merge several devices into one view
+  -b, --buffer <LIST> Logcat buffers to read, e.g. main,system,crash
+                      (main, system, crash, events, radio, kernel or all)
+  -u, --uid           Read logcat with -v uid, for uid: and user: filters
+  -C, --context <N>   Show N entries around each tag/query filter match
+  -h, --help          Print this help
+  -V, --version       Print version";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogSource {
+    Adb,
+    File(PathBuf),
+    Stdin,
+}
+
+impl LogSource {
+    pub fn is_live(&self) -> bool {
+        matches!(self, Self::Adb)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub source: LogSource,
+    pub serials: Vec<String>,
+    pub buffers: Vec<LogBuffer>,
+    pub context: usize,
+    pub uid: bool,
+}
+
+pub enum ParseOutcome {
+    Run(Args),
+    Exit(String),
+}
+
+pub fn parse_args() -> Result<ParseOutcome, String> {
+    parse_from(env::args().skip(1))
+}
+
+fn parse_from(mut args: impl Iterator<Item = String>) -> Result<ParseOutcome, String> {
+    let mut source = LogSource::Adb;
+    let mut serials = Vec::new();
+    let mut buffers = Vec::new();
+    let mut context = 0;
+    let mut uid = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(ParseOutcome::Exit(USAGE.to_string())),
+            "-V" | "--version" => {
+                return Ok(ParseOutcome::Exit(format!(
+                    "colored-logcat {}",
+                    env!("CARGO_PKG_VERSION")
+                )))
+            }
+            "-f" | "--file" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("{} requires a path\n\n{}", arg, USAGE))?;
+                source = file_source(path);
+            }
+            "-s" | "--serial" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} requires a device serial\n\n{}", arg, USAGE))?;
+                serials.push(value);
+            }
+            "-b" | "--buffer" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} requires a buffer list\n\n{}", arg, USAGE))?;
+                add_buffers(&mut buffers, &value)?;
+            }
+            "-u" | "--uid" => uid = true,
+            "-C" | "--context" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} requires a number\n\n{}", arg, USAGE))?;
+                context = parse_context(&value)?;
+            }
+            _ => {
+                if let Some(path) = arg.strip_prefix("--file=") {
+                    source = file_source(path.to_string());
+                } else if let Some(value) = arg.strip_prefix("--serial=") {
+                    serials.push(value.to_string());
+                } else if let Some(value) = arg.strip_prefix("--buffer=") {
+                    add_buffers(&mut buffers, value)?;
+                } else if let Some(value) = arg.strip_prefix("--context=") {
+                    context = parse_context(value)?;
+                } else {
+                    return Err(format!("Unknown argument: {}\n\n{}", arg, USAGE));
+                }
+            }
+        }
+    }
+
+    Ok(ParseOutcome::Run(Args {
+        source,
+        serials,
+        buffers,
+        context,
+        uid,
+    }))
+}
+
+fn parse_context(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n <= MAX_CONTEXT => Ok(n),
+        _ => Err(format!(
+            "Invalid context '{}': expected 0 to {}\n\n{}",
+            value, MAX_CONTEXT, USAGE
+        )),
+    }
+}
+
+fn add_buffers(buffers: &mut Vec<LogBuffer>, list: &str) -> Result<(), String> {
+    for buffer in LogBuffer::parse_list(list)? {
+        if !buffers.contains(&buffer) {
+            buffers.push(buffer);
+        }
+    }
+    Ok(())
+}
+
+fn file_source(path: String) -> LogSource {
+    if path == "-" {
+        LogSource::Stdin
+    } else {
+        LogSource::File(PathBuf::from(path))
+    }
+}
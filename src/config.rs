@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crate::preset::{self, Preset};
+
+/// Settings kept between runs, stored as JSON in the user's config directory
+/// (`%APPDATA%\colored-logcat\config.json`, or `~/.config/colored-logcat/`).
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub exclude_tags: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    /// Past prompt entries by prompt name (`filter`, `tag`, ...), oldest first.
+    pub history: BTreeMap<String, Vec<String>>,
+    /// Personal filter presets; shared ones live in `preset::PROJECT_FILE`.
+    pub presets: Vec<Preset>,
+}
+
+impl Config {
+    /// Read the config file. A missing file is the default config; an
+    /// unreadable one is reported but still leaves the defaults usable.
+    pub fn load() -> (Self, Option<String>) {
+        let Some(path) = config_path() else {
+            return (Self::default(), None);
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return (Self::default(), None),
+        };
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) => (Self::from_json(&value), None),
+            Err(e) => (
+                Self::default(),
+                Some(format!("Ignoring {}: {}", path.display(), e)),
+            ),
+        }
+    }
+
+    /// Write the config, keeping any other keys in the file. A file that does
+    /// not parse is left alone rather than replaced with what was loaded
+    /// instead of it.
+    pub fn save(&self) -> Result<(), String> {
+        let path = config_path().ok_or("No config directory (HOME/APPDATA unset)")?;
+        let mut root = read_object(&path)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        if let Value::Object(values) = self.to_json() {
+            root.extend(values);
+        }
+        write_object(&path, root)
+    }
+
+    fn from_json(value: &Value) -> Self {
+        Self {
+            exclude_tags: strings(&value["exclude"]["tags"]),
+            exclude_patterns: strings(&value["exclude"]["patterns"]),
+            history: value["history"]
+                .as_object()
+                .map(|prompts| {
+                    prompts
+                        .iter()
+                        .map(|(prompt, entries)| (prompt.clone(), strings(entries)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            presets: preset::parse_list(&value["presets"], false),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "exclude": {
+                "tags": self.exclude_tags,
+                "patterns": self.exclude_patterns,
+            },
+            "history": self.history,
+            "presets": self.presets.iter().map(Preset::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The JSON object in `path`, empty if there is no file. Anything else is an
+/// error, so the file is not overwritten.
+pub fn read_object(path: &Path) -> Result<Map<String, Value>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Map::new()),
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+    };
+    match serde_json::from_str::<Value>(&text) {
+        Ok(Value::Object(root)) => Ok(root),
+        _ => Err(format!("{} is not a JSON object; fix it to save again", path.display())),
+    }
+}
+
+pub fn write_object(path: &Path, root: Map<String, Value>) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(&Value::Object(root)).map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn config_path() -> Option<PathBuf> {
+    let dir = env::var_os("APPDATA")
+        .or_else(|| env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("colored-logcat").join("config.json"))
+}
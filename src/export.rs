@@ -15,8 +15,8 @@ pub fn export_logs(entries: &[&LogEntry], dir: Option<&str>) -> Result<PathBuf,
     let mut file = fs::File::create(&path)
         .map_err(|e| format!("Failed to create file: {}", e))?;
 
-    for entry in entries {
-        writeln!(file, "{}", entry.raw)
+    for line in entries.iter().flat_map(|entry| entry.raw_lines()) {
+        writeln!(file, "{}", line)
             .map_err(|e| format!("Failed to write: {}", e))?;
     }
 
@@ -1,55 +1,153 @@
 use regex::Regex;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 
-use crate::parser::{LogEntry, LogLevel};
+use crate::parser::{parse_divider, EntryKind, LogBuffer, LogEntry, LogLevel};
+use crate::process::ProcessTable;
+use crate::query::{compile_regex, MatchOptions, Query};
+use crate::timestamp::{TimeValue, Timestamp};
 
 static CRASH_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)(AndroidRuntime|FATAL EXCEPTION|FATAL|ANR|crash|System\.err)")
         .unwrap()
 });
 
+/// A process of the package filter. A PID is only the package's between
+/// taking it and another process reusing it.
+pub struct PackagePid {
+    pub pid: u32,
+    since: Option<Timestamp>, // exclusive; None = from the start of the buffer
+    until: Option<Timestamp>, // exclusive; None = still the package's
+}
+
+impl PackagePid {
+    fn covers(&self, entry: &LogEntry) -> bool {
+        self.pid == entry.pid
+            && self.since.as_ref().is_none_or(|t| entry.timestamp.cmp_time(t).is_gt())
+            && self.until.as_ref().is_none_or(|t| entry.timestamp.cmp_time(t).is_lt())
+    }
+}
+
 pub struct FilterSet {
-    pub level_toggles: [bool; 6],
-    pub tag_filter: Option<String>,
-    pub regex_filter: Option<Regex>,
-    pub pid_filter: Option<u32>,
+    pub level_toggles: [bool; 7],
+    tag_filter: Option<(String, Regex)>, // as typed, and compiled with match_options
+    tag_exact: bool, // tag_filter is an isolated tag's `~^tag$`, compiled as is
+    pub match_options: MatchOptions,
+    pub query: Option<Query>,
+    pub pid_filter: Vec<PackagePid>, // processes of the package filter; empty = any
+    pub uid_filter: Vec<u32>, // the package's uids (`U`); empty = any
+    /// Device and tid isolated with middle click / `I`.
+    pub thread_filter: Option<(Option<Arc<str>>, u32)>,
+    pub device_filter: Option<String>,
+    /// Tags hidden outright, e.g. `chatty`.
+    pub exclude_tags: Vec<String>,
+    /// Lines hidden when the message or tag matches.
+    pub exclude_patterns: Vec<Regex>,
 }
 
 impl Default for FilterSet {
     fn default() -> Self {
         Self {
-            level_toggles: [true; 6],
+            level_toggles: [true; 7],
             tag_filter: None,
-            regex_filter: None,
-            pid_filter: None,
+            tag_exact: false,
+            match_options: MatchOptions::default(),
+            query: None,
+            pid_filter: Vec::new(),
+            uid_filter: Vec::new(),
+            thread_filter: None,
+            device_filter: None,
+            exclude_tags: Vec::new(),
+            exclude_patterns: Vec::new(),
         }
     }
 }
 
+/// Compile the `t` prompt's text. Tags such as `Outer$Inner` or `chromium[1]`
+/// are common, so the text is taken literally unless it starts with `~`;
+/// smart case and whole word apply either way.
+pub fn tag_regex(text: &str, options: &MatchOptions) -> Result<Regex, String> {
+    match text.strip_prefix('~') {
+        Some(pattern) => MatchOptions { literal: false, ..*options }.compile(pattern),
+        None => MatchOptions { literal: true, ..*options }.compile(text),
+    }
+}
+
 impl FilterSet {
-    pub fn matches(&self, entry: &LogEntry) -> bool {
+    pub fn matches(&self, entry: &LogEntry, processes: &ProcessTable) -> bool {
+        self.matches_base(entry) && self.matches_pattern(entry, processes)
+    }
+
+    /// Everything but the tag filter and query: levels, excludes, PID and
+    /// device. Lines passing only these can still be shown as context.
+    pub fn matches_base(&self, entry: &LogEntry) -> bool {
+        // Markers keep the timeline readable under any filter
+        if entry.kind == EntryKind::Marker {
+            return true;
+        }
+
         // Check level toggle
         if !self.level_toggles[entry.level.index()] {
             return false;
         }
 
-        // Check tag filter
-        if let Some(ref tag) = self.tag_filter {
-            if !entry.tag.contains(tag.as_str()) {
+        // Check exclude lists
+        if self.exclude_tags.contains(&entry.tag) {
+            return false;
+        }
+        if self.exclude_patterns.iter().any(|re| {
+            re.is_match(&entry.tag)
+                || std::iter::once(entry)
+                    .chain(&entry.folded)
+                    .any(|e| re.is_match(&e.message))
+        }) {
+            return false;
+        }
+
+        // Check PID filter
+        if !self.pid_filter.is_empty() && !self.pid_filter.iter().any(|p| p.covers(entry)) {
+            return false;
+        }
+
+        // Check thread filter
+        if let Some((device, tid)) = &self.thread_filter {
+            if entry.tid != *tid || entry.device != *device {
                 return false;
             }
         }
 
-        // Check regex filter
-        if let Some(ref re) = self.regex_filter {
-            if !re.is_match(&entry.message) && !re.is_match(&entry.tag) {
+        // Check UID filter
+        if !self.uid_filter.is_empty()
+            && !entry.uid.is_some_and(|uid| self.uid_filter.contains(&uid))
+        {
+            return false;
+        }
+
+        // Check device filter
+        if let Some(ref device) = self.device_filter {
+            if entry.device.as_deref() != Some(device.as_str()) {
                 return false;
             }
         }
 
-        // Check PID filter
-        if let Some(pid) = self.pid_filter {
-            if entry.pid != pid {
+        true
+    }
+
+    /// The tag filter and query, the filters context lines are shown around.
+    pub fn matches_pattern(&self, entry: &LogEntry, processes: &ProcessTable) -> bool {
+        if entry.kind == EntryKind::Marker {
+            return true;
+        }
+
+        // Check tag filter
+        if let Some((_, ref re)) = self.tag_filter {
+            if !re.is_match(&entry.tag) {
+                return false;
+            }
+        }
+
+        // Check query
+        if let Some(ref query) = self.query {
+            if !query.matches(entry, processes) {
                 return false;
             }
         }
@@ -57,12 +155,113 @@ impl FilterSet {
         true
     }
 
-    pub fn set_regex(&mut self, pattern: &str) {
-        if pattern.is_empty() {
-            self.regex_filter = None;
+    pub fn has_pattern(&self) -> bool {
+        self.tag_filter.is_some() || self.query.is_some()
+    }
+
+    /// Replace the query; on a parse error the previous one stays in effect.
+    /// `last:` terms count back from `now`.
+    pub fn set_query(&mut self, source: &str, now: TimeValue) -> Result<(), String> {
+        self.query = if source.trim().is_empty() {
+            None
         } else {
-            self.regex_filter = Regex::new(pattern).ok();
+            let mut query = Query::parse(source, &self.match_options)?;
+            query.resolve_last(now);
+            Some(query)
+        };
+        Ok(())
+    }
+
+    pub fn tag_filter(&self) -> Option<&str> {
+        self.tag_filter.as_ref().map(|(text, _)| text.as_str())
+    }
+
+    /// Replace the tag filter: text the tag contains, or `~regex`.
+    pub fn set_tag_filter(&mut self, tag: Option<&str>) -> Result<(), String> {
+        self.tag_filter = match tag.filter(|t| !t.is_empty()) {
+            Some(tag) => Some((tag.to_string(), tag_regex(tag, &self.match_options)?)),
+            None => None,
+        };
+        self.tag_exact = false;
+        Ok(())
+    }
+
+    /// Show only `tag`, spelled exactly so. Unlike a typed tag filter this
+    /// ignores the match options, which would make it a substring or
+    /// case-insensitive match.
+    pub fn set_exact_tag_filter(&mut self, tag: &str) {
+        let pattern = format!("^{}$", regex::escape(tag));
+        let re = Regex::new(&pattern).expect("escaped tag is a valid pattern");
+        self.tag_filter = Some((format!("~{}", pattern), re));
+        self.tag_exact = true;
+    }
+
+    /// Whether the tag filter is `tag` set by `set_exact_tag_filter`.
+    pub fn is_exact_tag(&self, tag: &str) -> bool {
+        self.tag_exact && self.tag_filter() == Some(format!("~^{}$", regex::escape(tag)).as_str())
+    }
+
+    /// Change the match options and recompile the query and tag filter with
+    /// them. If either no longer compiles, nothing changes.
+    pub fn set_match_options(&mut self, options: MatchOptions) -> Result<(), String> {
+        let query = match self.query {
+            Some(ref query) => {
+                // Re-parsing would move `last:` forward; keep the range it had
+                let mut reparsed = Query::parse(&query.source, &options)?;
+                if let Some(now) = query.resolved_at() {
+                    reparsed.resolve_last(now);
+                }
+                Some(reparsed)
+            }
+            None => None,
+        };
+        let tag = match self.tag_filter {
+            Some(ref exact) if self.tag_exact => Some(exact.clone()),
+            Some((ref text, _)) => Some((text.clone(), tag_regex(text, &options)?)),
+            None => None,
+        };
+        self.match_options = options;
+        self.query = query;
+        self.tag_filter = tag;
+        Ok(())
+    }
+
+    /// PIDs the package filter currently follows.
+    pub fn package_pids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.pid_filter
+            .iter()
+            .filter(|p| p.until.is_none())
+            .map(|p| p.pid)
+    }
+
+    /// Follow `pid`, the package's after `at`; returns false if it already
+    /// is. `at` only matters for a PID another process had in between, whose
+    /// lines stay hidden.
+    pub fn add_package_pid(&mut self, pid: u32, at: &Timestamp) -> bool {
+        if self.package_pids().any(|p| p == pid) {
+            return false;
         }
+        let reused = self.pid_filter.iter().any(|p| p.pid == pid);
+        self.pid_filter.push(PackagePid {
+            pid,
+            since: reused.then(|| at.clone()),
+            until: None,
+        });
+        true
+    }
+
+    /// Another process has `pid` from `at` on: stop showing its lines from
+    /// there. Returns false if the package filter does not follow `pid`.
+    pub fn end_package_pid(&mut self, pid: u32, at: &Timestamp) -> bool {
+        let Some(entry) = self
+            .pid_filter
+            .iter_mut()
+            .find(|p| p.pid == pid && p.until.is_none())
+        else {
+            return false;
+        };
+        entry.until = Some(at.clone());
+        true
     }
 
     pub fn toggle_level(&mut self, level: LogLevel) {
@@ -70,11 +269,71 @@ impl FilterSet {
         self.level_toggles[idx] = !self.level_toggles[idx];
     }
 
+    /// Parse the exclude prompt: tags separated by spaces, and `~regex` (or
+    /// `~"regex with spaces"`) for message patterns. Nothing changes on error.
+    pub fn set_excludes(&mut self, text: &str) -> Result<(), String> {
+        let mut tags = Vec::new();
+        let mut patterns = Vec::new();
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let (item, is_pattern, after) = if let Some(quoted) = rest.strip_prefix("~\"") {
+                let end = quoted.find('"').ok_or("Unclosed '\"' in exclude list")?;
+                (&quoted[..end], true, &quoted[end + 1..])
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                match rest[..end].strip_prefix('~') {
+                    Some(pattern) => (pattern, true, &rest[end..]),
+                    None => (&rest[..end], false, &rest[end..]),
+                }
+            };
+            if is_pattern && item.is_empty() {
+                return Err("Empty exclude regex after '~'".to_string());
+            } else if is_pattern {
+                patterns.push(compile_regex(item)?);
+            } else if !tags.iter().any(|t| t == item) {
+                tags.push(item.to_string());
+            }
+            rest = after.trim_start();
+        }
+        self.exclude_tags = tags;
+        self.exclude_patterns = patterns;
+        Ok(())
+    }
+
+    /// The exclude lists in the syntax `set_excludes` reads.
+    pub fn exclude_text(&self) -> String {
+        let patterns = self.exclude_patterns.iter().map(|re| {
+            if re.as_str().contains(char::is_whitespace) {
+                format!("~\"{}\"", re.as_str())
+            } else {
+                format!("~{}", re.as_str())
+            }
+        });
+        self.exclude_tags
+            .iter()
+            .cloned()
+            .chain(patterns)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Clear every filter except the exclude lists, which are standing
+    /// noise suppression kept between runs.
     pub fn reset(&mut self) {
-        *self = Self::default();
+        *self = Self {
+            exclude_tags: std::mem::take(&mut self.exclude_tags),
+            exclude_patterns: std::mem::take(&mut self.exclude_patterns),
+            match_options: self.match_options,
+            ..Self::default()
+        };
     }
 }
 
 pub fn is_crash_entry(entry: &LogEntry) -> bool {
-    entry.level >= LogLevel::Error || CRASH_RE.is_match(&entry.raw)
+    // "--------- beginning of crash" names the buffer without being a crash
+    if entry.kind == EntryKind::Unparsed && parse_divider(&entry.raw).is_some() {
+        return false;
+    }
+    entry.buffer == Some(LogBuffer::Crash)
+        || entry.kind == EntryKind::Log && entry.level >= LogLevel::Error || CRASH_RE.is_match(&entry.raw)
 }
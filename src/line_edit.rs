@@ -0,0 +1,129 @@
+use std::ops::Deref;
+
+/// Prompt text with a cursor, edited the way a shell line is: arrows, Home/End,
+/// word jumps and Ctrl-W/U/K. Derefs to the text.
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize, // byte offset, always on a char boundary
+}
+
+impl Deref for LineEditor {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl LineEditor {
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the text, with the cursor at its end.
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert pasted text; line breaks become spaces since prompts are one line.
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| if c == '\r' || c == '\n' || c == '\t' { ' ' } else { c })
+            .collect();
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    pub fn backspace(&mut self) {
+        let start = self.prev_char();
+        self.text.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    pub fn delete(&mut self) {
+        let end = self.next_char();
+        self.text.drain(self.cursor..end);
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.prev_char();
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = self.next_char();
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.prev_word();
+    }
+
+    pub fn word_right(&mut self) {
+        let rest = &self.text[self.cursor..];
+        let word = rest.len() - rest.trim_start().len();
+        let end = rest[word..]
+            .find(char::is_whitespace)
+            .map_or(rest.len(), |i| word + i);
+        self.cursor += end;
+    }
+
+    /// Ctrl-W: delete back to the start of the word before the cursor.
+    pub fn delete_word(&mut self) {
+        let start = self.prev_word();
+        self.text.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Ctrl-U: delete everything before the cursor.
+    pub fn delete_to_start(&mut self) {
+        self.text.drain(..self.cursor);
+        self.cursor = 0;
+    }
+
+    /// Ctrl-K: delete everything after the cursor.
+    pub fn delete_to_end(&mut self) {
+        self.text.truncate(self.cursor);
+    }
+
+    fn prev_char(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_char(&self) -> usize {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// Start of the word before the cursor, skipping the spaces in between.
+    fn prev_word(&self) -> usize {
+        let before = self.text[..self.cursor].trim_end();
+        before
+            .rfind(char::is_whitespace)
+            .map_or(0, |i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+    }
+}
@@ -1,10 +1,19 @@
 mod adb;
 mod app;
+mod cli;
 mod clipboard;
+mod config;
 mod export;
 mod filter;
 mod json;
+mod line_edit;
+mod merge;
 mod parser;
+mod preset;
+mod process;
+mod query;
+mod replay;
+mod timestamp;
 mod ui;
 
 use std::io;
@@ -12,8 +21,8 @@ use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
 use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
-    MouseButton, MouseEventKind,
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
 };
 use crossterm::execute;
 use crossterm::terminal::{
@@ -23,24 +32,47 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::layout::Rect;
 use ratatui::Terminal;
 
-use app::{App, InputMode};
+use app::{App, InputMode, PanelLayout};
+use cli::{Args, ParseOutcome};
 use parser::LogLevel;
 
 const MOUSE_SCROLL_LINES: usize = 1;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = match cli::parse_args() {
+        Ok(ParseOutcome::Run(args)) => args,
+        Ok(ParseOutcome::Exit(text)) => {
+            println!("{}", text);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_app(&mut terminal);
+    let result = run_app(&mut terminal, args);
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
     terminal.show_cursor()?;
 
     if let Err(e) = result {
@@ -52,46 +84,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    args: Args,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut app = App::new();
-
-    // Start ADB logcat reader
     let (tx, rx) = mpsc::channel();
-    let mut _adb_handle = match adb::spawn_logcat(tx) {
-        Ok(handle) => handle,
-        Err(e) => {
-            app.status_message = Some(format!("ADB error: {}", e));
-            // Still start the app so user can see the error
-            // Create a dummy channel
-            let (_dummy_tx, _) = mpsc::channel::<adb::AdbMessage>();
-            // We'll just render with no log input
-            loop {
-                terminal.draw(|f| ui::render(f, &app))?;
-                if event::poll(Duration::from_millis(100))? {
-                    match event::read()? {
-                        Event::Key(key) => {
-                            if key.kind == KeyEventKind::Press
-                                && (key.code == KeyCode::Char('q') || key.code == KeyCode::Esc)
-                            {
-                                return Ok(());
-                            }
-                        }
-                        Event::Mouse(mouse) => {
-                            let terminal_size = terminal.size()?;
-                            let terminal_area =
-                                Rect::new(0, 0, terminal_size.width, terminal_size.height);
-                            let log_area = ui::log_view_area(terminal_area, &app);
-                            handle_mouse(&mut app, mouse, log_area);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-    };
+    let mut app = App::new(&args, tx);
+
+    // Still start the app on failure so the user can see the error
+    if let Err(e) = app.start_stream() {
+        app.status_message = Some(e);
+    }
 
     let mut last_pid_poll = Instant::now();
     let pid_poll_interval = Duration::from_secs(2);
+    let mut last_process_poll = Instant::now();
+    let process_poll_interval = Duration::from_secs(5);
+    let mut last_thread_poll = Instant::now();
+    app.poll_processes();
 
     'app_loop: loop {
         // Drain all available log entries (batched for performance)
@@ -99,15 +107,27 @@ fn run_app(
         while let Ok(msg) = rx.try_recv() {
             match msg {
                 adb::AdbMessage::Entry(entry) => {
-                    app.add_entry(entry);
+                    app.ingest(entry);
                     new_entries += 1;
                 }
-                adb::AdbMessage::UnparsedLine => {
-                    // Skip unparsed lines (beginning-of-logcat header, etc.)
+                adb::AdbMessage::Disconnected(serial, reason) => {
+                    app.handle_disconnect(serial, reason)
+                }
+                adb::AdbMessage::DeviceReady(serial, result) => {
+                    app.handle_device_ready(serial, result)
+                }
+                adb::AdbMessage::Devices(devices) => app.set_devices(devices),
+                adb::AdbMessage::PackageProcesses(package, processes) => {
+                    app.handle_package_processes(&package, processes)
+                }
+                adb::AdbMessage::PackageUids(package, uids) => {
+                    app.handle_package_uids(&package, uids)
                 }
-                adb::AdbMessage::Disconnected(reason) => {
-                    app.status_message = Some(reason);
+                adb::AdbMessage::Packages(packages) => app.handle_packages(packages),
+                adb::AdbMessage::Processes(serial, processes) => {
+                    app.handle_processes(serial, processes)
                 }
+                adb::AdbMessage::Threads(serial, threads) => app.handle_threads(serial, threads),
             }
             // Batch limit: process max 1000 per frame to keep UI responsive
             if new_entries >= 1000 {
@@ -115,15 +135,29 @@ fn run_app(
             }
         }
 
-        // Poll for package PID if needed
-        if app.package_filter.is_some()
-            && app.filters.pid_filter.is_none()
-            && last_pid_poll.elapsed() >= pid_poll_interval
-        {
-            app.poll_package_pid();
+        // Release merged entries held back waiting for slower devices
+        app.flush_merged();
+
+        // Keep polling while a package filter is set, to follow restarts
+        if app.package_filter.is_some() && last_pid_poll.elapsed() >= pid_poll_interval {
+            app.poll_package_pids();
             last_pid_poll = Instant::now();
         }
 
+        // Thread names for the processes on screen, when that column is shown
+        if last_thread_poll.elapsed() >= pid_poll_interval {
+            let size = terminal.size()?;
+            let area = ui::log_view_area(Rect::new(0, 0, size.width, size.height), &app);
+            app.poll_threads(area.height.saturating_sub(2) as usize);
+            last_thread_poll = Instant::now();
+        }
+
+        // Keep process names current for new and restarted processes
+        if last_process_poll.elapsed() >= process_poll_interval {
+            app.poll_processes();
+            last_process_poll = Instant::now();
+        }
+
         // Render
         terminal.draw(|f| ui::render(f, &app))?;
 
@@ -131,22 +165,12 @@ fn run_app(
         if event::poll(Duration::from_millis(16))? {
             let terminal_size = terminal.size()?;
             let terminal_area = Rect::new(0, 0, terminal_size.width, terminal_size.height);
-            let log_area = ui::log_view_area(terminal_area, &app);
-            if handle_event_with_area(
-                &mut app,
-                event::read()?,
-                log_area,
-            ) {
+            if handle_event_with_area(&mut app, event::read()?, terminal_area) {
                 break;
             }
 
             while event::poll(Duration::from_millis(0))? {
-                let log_area = ui::log_view_area(terminal_area, &app);
-                if handle_event_with_area(
-                    &mut app,
-                    event::read()?,
-                    log_area,
-                ) {
+                if handle_event_with_area(&mut app, event::read()?, terminal_area) {
                     break 'app_loop;
                 }
             }
@@ -155,7 +179,13 @@ fn run_app(
 
     Ok(())
 }
+
 fn handle_mouse(app: &mut App, mouse: crossterm::event::MouseEvent, log_area: ratatui::layout::Rect) {
+    if mouse.kind == MouseEventKind::Moved {
+        app.mouse_position = Some((mouse.column, mouse.row));
+        return;
+    }
+
     if let Some(absolute_idx) = mouse_log_entry(app, mouse.column, mouse.row, log_area) {
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
@@ -176,6 +206,10 @@ fn handle_mouse(app: &mut App, mouse: crossterm::event::MouseEvent, log_area: ra
                 }
                 return;
             }
+            MouseEventKind::Down(MouseButton::Middle) => {
+                app.isolate_thread(absolute_idx);
+                return;
+            }
             _ => {}
         }
     } else if matches!(mouse.kind, MouseEventKind::Up(MouseButton::Left)) {
@@ -193,7 +227,7 @@ fn handle_mouse(app: &mut App, mouse: crossterm::event::MouseEvent, log_area: ra
     }
 }
 
-fn handle_event_with_area(app: &mut App, event: Event, log_area: ratatui::layout::Rect) -> bool {
+fn handle_event_with_area(app: &mut App, event: Event, terminal_area: Rect) -> bool {
     match event {
         Event::Key(key) => {
             if key.kind != KeyEventKind::Press {
@@ -205,18 +239,62 @@ fn handle_event_with_area(app: &mut App, event: Event, log_area: ratatui::layout
                 return true;
             }
 
+            // Match option toggles work while typing a pattern, too
+            if key.modifiers.contains(KeyModifiers::ALT) {
+                let mut options = app.filters.match_options;
+                let flag = match key.code {
+                    KeyCode::Char('c') => Some(&mut options.smart_case),
+                    KeyCode::Char('r') => Some(&mut options.literal),
+                    KeyCode::Char('w') => Some(&mut options.whole_word),
+                    _ => None,
+                };
+                if let Some(flag) = flag {
+                    *flag = !*flag;
+                    app.set_match_options(options);
+                    return false;
+                }
+                // Other Alt keys are word moves in the prompts
+                if app.input_mode == InputMode::Normal {
+                    return false;
+                }
+            }
+
             match app.input_mode {
-                InputMode::Normal => handle_normal_key(app, key),
-                InputMode::Filter | InputMode::Tag | InputMode::Package => {
-                    handle_input_key(app, key.code);
+                InputMode::Normal => {
+                    // Resolve now: a tailing view moves lines under a still mouse
+                    app.hovered = app.mouse_position.and_then(|(column, row)| {
+                        mouse_log_entry(app, column, row, ui::log_view_area(terminal_area, app))
+                    });
+                    handle_normal_key(app, key)
+                }
+                InputMode::Filter
+                | InputMode::Tag
+                | InputMode::Exclude
+                | InputMode::Package
+                | InputMode::Buffers
+                | InputMode::Search
+                | InputMode::Goto
+                | InputMode::PresetName => {
+                    handle_input_key(app, key);
                 }
             }
 
             app.should_quit
         }
+        Event::Paste(text) => {
+            if app.input_mode != InputMode::Normal {
+                app.filter_input.insert_str(&text);
+                app.validate_input();
+            }
+            false
+        }
         Event::Mouse(mouse) => {
             if matches!(app.input_mode, InputMode::Normal) {
-                handle_mouse(app, mouse, log_area);
+                if let Some(row) = mouse_device_row(app, mouse, terminal_area) {
+                    app.select_device(row / ui::device_panel::ROWS_PER_DEVICE);
+                } else {
+                    handle_mouse(app, mouse, ui::log_view_area(terminal_area, app));
+                }
             }
             false
         }
@@ -225,24 +303,39 @@ fn handle_event_with_area(app: &mut App, event: Event, log_area: ratatui::layout
 }
 
 fn handle_normal_key(app: &mut App, key: crossterm::event::KeyEvent) {
+    if app.show_presets && handle_preset_key(app, key.code) {
+        return;
+    }
+    if app.device_focus && handle_device_key(app, key.code) {
+        return;
+    }
+
     match key.code {
         KeyCode::Char('q') => app.should_quit = true,
         KeyCode::Char('h') | KeyCode::Char('?') => app.show_help = !app.show_help,
         KeyCode::Char('y') => app.copy_selection(),
+        KeyCode::Char('e') => app.toggle_expand_selection(),
+        KeyCode::Char('E') => app.toggle_expand_all(),
+        KeyCode::Char('P') => app.cycle_name_columns(),
+        KeyCode::Char('[') => app.adjust_context(-1),
+        KeyCode::Char(']') => app.adjust_context(1),
+        KeyCode::Char('m') => app.mute_target_tag(),
+        KeyCode::Char('i') => app.isolate_target_tag(),
+        KeyCode::Char('I') => app.isolate_target_thread(),
+        KeyCode::Char('u') => app.undo_mute(),
 
         // Filter modes
-        KeyCode::Char('/') => {
-            app.input_mode = InputMode::Filter;
-            app.filter_input.clear();
-        }
-        KeyCode::Char('t') => {
-            app.input_mode = InputMode::Tag;
-            app.filter_input.clear();
-        }
-        KeyCode::Char('p') => {
-            app.input_mode = InputMode::Package;
-            app.filter_input.clear();
-        }
+        KeyCode::Char('/') => app.begin_filter_input(),
+        KeyCode::Char('t') => app.begin_input(InputMode::Tag, String::new()),
+        KeyCode::Char('T') => app.begin_exclude_input(),
+        KeyCode::Char('U') => app.toggle_package_uid_filter(),
+        KeyCode::Char('p') => app.begin_input(InputMode::Package, String::new()),
+        KeyCode::Char('b') => app.begin_buffer_input(),
+        KeyCode::Char('g') => app.begin_goto_input(),
+        KeyCode::Char('F') => app.toggle_presets(),
+        KeyCode::Char('f') => app.begin_search_input(),
+        KeyCode::Char('n') => app.jump_to_match(true),
+        KeyCode::Char('N') => app.jump_to_match(false),
 
         // Level toggles
         KeyCode::Char('1') => app.toggle_level(LogLevel::Verbose),
@@ -251,6 +344,7 @@ fn handle_normal_key(app: &mut App, key: crossterm::event::KeyEvent) {
         KeyCode::Char('4') => app.toggle_level(LogLevel::Warn),
         KeyCode::Char('5') => app.toggle_level(LogLevel::Error),
         KeyCode::Char('6') => app.toggle_level(LogLevel::Fatal),
+        KeyCode::Char('7') => app.toggle_level(LogLevel::Unknown),
 
         // Scrolling
         KeyCode::Char(' ') => {
@@ -272,6 +366,7 @@ fn handle_normal_key(app: &mut App, key: crossterm::event::KeyEvent) {
         KeyCode::Char('s') => app.export_logs(),
         KeyCode::Char('d') => app.toggle_device_panel(),
         KeyCode::Char('x') => app.toggle_crash_panel(),
+        KeyCode::Tab if app.panels == PanelLayout::SplitDevice => app.device_focus = true,
 
         // Clear all filters
         KeyCode::Esc => {
@@ -280,6 +375,9 @@ fn handle_normal_key(app: &mut App, key: crossterm::event::KeyEvent) {
             } else if app.selection.is_some() {
                 app.clear_selection();
                 app.status_message = Some("Selection cleared".to_string());
+            } else if app.search.is_some() {
+                app.clear_search();
+                app.status_message = Some("Search cleared".to_string());
             } else {
                 app.clear_all_filters();
                 app.status_message = Some("Filters cleared".to_string());
@@ -290,6 +388,35 @@ fn handle_normal_key(app: &mut App, key: crossterm::event::KeyEvent) {
     }
 }
 
+/// Keys handled while the device panel has focus. Returns false to fall through.
+fn handle_device_key(app: &mut App, key: KeyCode) -> bool {
+    match key {
+        KeyCode::Up | KeyCode::Char('k') => app.move_device_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_device_cursor(1),
+        KeyCode::Enter => app.select_device(app.device_cursor),
+        KeyCode::Char('m') => app.toggle_merge_cursor_device(),
+        KeyCode::Char('f') => app.toggle_device_filter(),
+        KeyCode::Tab | KeyCode::Esc => app.device_focus = false,
+        _ => return false,
+    }
+    true
+}
+
+/// Keys of the preset picker; anything else falls through to the log view.
+fn handle_preset_key(app: &mut App, key: KeyCode) -> bool {
+    match key {
+        KeyCode::Up | KeyCode::Char('k') => app.move_preset_cursor(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_preset_cursor(1),
+        KeyCode::Enter => app.apply_preset(),
+        KeyCode::Char('s') => app.begin_input(InputMode::PresetName, String::new()),
+        KeyCode::Char('d') => app.delete_preset(),
+        KeyCode::Char('e') => app.toggle_preset_shared(),
+        KeyCode::Char('F') | KeyCode::Esc => app.show_presets = false,
+        _ => return false,
+    }
+    true
+}
+
 fn mouse_log_entry(app: &App, column: u16, row: u16, log_area: ratatui::layout::Rect) -> Option<usize> {
     if column <= log_area.x
         || column >= log_area.x + log_area.width.saturating_sub(1)
@@ -304,16 +431,64 @@ fn mouse_log_entry(app: &App, column: u16, row: u16, log_area: ratatui::layout::
     app.visible_entry_at_row(inner_height, content_row)
 }
 
-fn handle_input_key(app: &mut App, key: KeyCode) {
-    match key {
-        KeyCode::Enter => app.submit_filter_input(),
-        KeyCode::Esc => app.cancel_input(),
-        KeyCode::Backspace => {
-            app.filter_input.pop();
-        }
-        KeyCode::Char(c) => {
-            app.filter_input.push(c);
-        }
-        _ => {}
+/// Device panel row under a left click, if the device panel is open.
+fn mouse_device_row(
+    app: &App,
+    mouse: crossterm::event::MouseEvent,
+    terminal_area: Rect,
+) -> Option<usize> {
+    if app.panels != PanelLayout::SplitDevice
+        || !matches!(mouse.kind, MouseEventKind::Down(MouseButton::Left))
+    {
+        return None;
+    }
+
+    let area = ui::side_panel_area(terminal_area, app)?;
+    if mouse.column <= area.x
+        || mouse.column >= area.x + area.width.saturating_sub(1)
+        || mouse.row <= area.y
+        || mouse.row >= area.y + area.height.saturating_sub(1)
+    {
+        return None;
+    }
+    Some((mouse.row - area.y - 1) as usize)
+}
+
+/// Line editing in the prompts. Ctrl and Alt combinations follow readline.
+fn handle_input_key(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let input = &mut app.filter_input;
+    match key.code {
+        KeyCode::Enter => return app.submit_filter_input(),
+        KeyCode::Esc => return app.cancel_input(),
+        KeyCode::Up => return app.history_prev(),
+        KeyCode::Down => return app.history_next(),
+        KeyCode::Tab => return app.complete(true),
+        KeyCode::BackTab => return app.complete(false),
+        KeyCode::Left if ctrl || alt => input.word_left(),
+        KeyCode::Right if ctrl || alt => input.word_right(),
+        KeyCode::Left => input.left(),
+        KeyCode::Right => input.right(),
+        KeyCode::Home => input.home(),
+        KeyCode::End => input.end(),
+        KeyCode::Backspace if ctrl || alt => input.delete_word(),
+        KeyCode::Backspace => input.backspace(),
+        KeyCode::Delete => input.delete(),
+        KeyCode::Char('a') if ctrl => input.home(),
+        KeyCode::Char('e') if ctrl => input.end(),
+        KeyCode::Char('b') if ctrl => input.left(),
+        KeyCode::Char('f') if ctrl => input.right(),
+        KeyCode::Char('h') if ctrl => input.backspace(),
+        KeyCode::Char('w') if ctrl => input.delete_word(),
+        KeyCode::Char('u') if ctrl => input.delete_to_start(),
+        KeyCode::Char('k') if ctrl => input.delete_to_end(),
+        KeyCode::Char('d') if ctrl => input.delete(),
+        KeyCode::Char('b') if alt => input.word_left(),
+        KeyCode::Char('f') if alt => input.word_right(),
+        KeyCode::Char(_) if ctrl || alt => return,
+        KeyCode::Char(c) => input.insert(c),
+        _ => return,
     }
+    app.validate_input();
 }
@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::parser::LogEntry;
+
+/// How long an entry may wait for a quieter device before it is emitted anyway.
+const HOLDBACK: Duration = Duration::from_millis(300);
+
+struct DeviceQueue {
+    device: Option<Arc<str>>,
+    active: bool,
+    entries: VecDeque<(Instant, LogEntry)>,
+}
+
+/// K-way merge of per-device logcat streams by timestamp.
+///
+/// Each device's stream is already in time order, so the oldest queued head
+/// can be emitted once every active device has something queued (nothing
+/// older can still arrive), or once it has waited longer than `HOLDBACK`.
+#[derive(Default)]
+pub struct StreamMerger {
+    queues: Vec<DeviceQueue>,
+}
+
+impl StreamMerger {
+    /// Set which devices are streaming. Inactive devices only drain what is queued.
+    pub fn set_active(&mut self, devices: &[Option<Arc<str>>]) {
+        for queue in &mut self.queues {
+            queue.active = devices.contains(&queue.device);
+        }
+        for device in devices {
+            if !self.queues.iter().any(|q| &q.device == device) {
+                self.queues.push(DeviceQueue {
+                    device: device.clone(),
+                    active: true,
+                    entries: VecDeque::new(),
+                });
+            }
+        }
+        self.queues.retain(|q| q.active || !q.entries.is_empty());
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        let now = Instant::now();
+        match self.queues.iter_mut().find(|q| q.device == entry.device) {
+            Some(queue) => queue.entries.push_back((now, entry)),
+            None => self.queues.push(DeviceQueue {
+                device: entry.device.clone(),
+                active: false,
+                entries: VecDeque::from([(now, entry)]),
+            }),
+        }
+    }
+
+    /// Pop the next entry in timestamp order, if it is safe to emit.
+    pub fn pop_ready(&mut self, now: Instant) -> Option<LogEntry> {
+        let (oldest, queue) = self
+            .queues
+            .iter()
+            .enumerate()
+            .filter_map(|(i, q)| q.entries.front().map(|(_, e)| (i, e)))
+            .min_by(|(_, a), (_, b)| a.timestamp.cmp_time(&b.timestamp))
+            .map(|(i, _)| (i, &self.queues[i]))?;
+
+        let (arrived, _) = queue.entries.front()?;
+        let all_queued = self
+            .queues
+            .iter()
+            .all(|q| !q.active || !q.entries.is_empty());
+        if !all_queued && now.duration_since(*arrived) < HOLDBACK {
+            return None;
+        }
+
+        self.queues[oldest].entries.pop_front().map(|(_, e)| e)
+    }
+
+    /// Everything still queued, in timestamp order.
+    pub fn drain(&mut self) -> Vec<LogEntry> {
+        let mut entries: Vec<LogEntry> = self
+            .queues
+            .iter_mut()
+            .flat_map(|q| q.entries.drain(..).map(|(_, e)| e))
+            .collect();
+        entries.sort_by(|a, b| a.timestamp.cmp_time(&b.timestamp));
+        entries
+    }
+}
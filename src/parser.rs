@@ -1,16 +1,23 @@
+use chrono::{Local, NaiveDateTime};
 use regex::Regex;
 use serde_json::Value;
 use std::fmt;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use crate::process::parse_uid;
+use crate::timestamp::Timestamp;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
-    Verbose = 0,
-    Debug = 1,
-    Info = 2,
-    Warn = 3,
-    Error = 4,
-    Fatal = 5,
+    /// Lines that matched no logcat format; ranks below Verbose.
+    Unknown,
+    Verbose,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
 }
 
 impl LogLevel {
@@ -21,7 +28,7 @@ impl LogLevel {
             'I' => Some(Self::Info),
             'W' => Some(Self::Warn),
             'E' => Some(Self::Error),
-            'F' => Some(Self::Fatal),
+            'F' | 'A' => Some(Self::Fatal),
             _ => None,
         }
     }
@@ -34,11 +41,21 @@ impl LogLevel {
             Self::Warn => 'W',
             Self::Error => 'E',
             Self::Fatal => 'F',
+            Self::Unknown => '?',
         }
     }
 
+    /// Slot in per-level arrays; Unknown comes last so keys 1-6 stay V..F.
     pub fn index(self) -> usize {
-        self as usize
+        match self {
+            Self::Verbose => 0,
+            Self::Debug => 1,
+            Self::Info => 2,
+            Self::Warn => 3,
+            Self::Error => 4,
+            Self::Fatal => 5,
+            Self::Unknown => 6,
+        }
     }
 }
 
@@ -48,44 +65,382 @@ impl fmt::Display for LogLevel {
     }
 }
 
+/// A logcat ring buffer, as passed to `adb logcat -b`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogBuffer {
+    Main,
+    System,
+    Crash,
+    Events,
+    Radio,
+    Kernel,
+}
+
+impl LogBuffer {
+    pub const ALL: [LogBuffer; 6] = [
+        Self::Main,
+        Self::System,
+        Self::Crash,
+        Self::Events,
+        Self::Radio,
+        Self::Kernel,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|b| b.as_str() == name)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Main => "main",
+            Self::System => "system",
+            Self::Crash => "crash",
+            Self::Events => "events",
+            Self::Radio => "radio",
+            Self::Kernel => "kernel",
+        }
+    }
+
+    /// Parse a comma/space separated list such as `main,crash`. `all` selects
+    /// every buffer; an empty list means the device's default set.
+    pub fn parse_list(text: &str) -> Result<Vec<Self>, String> {
+        let mut buffers = Vec::new();
+        for name in text.split([',', ' ']).filter(|n| !n.is_empty()) {
+            if name == "all" {
+                return Ok(Self::ALL.to_vec());
+            }
+            let buffer = Self::from_name(name).ok_or_else(|| {
+                format!(
+                    "Unknown buffer '{}' (expected all, {})",
+                    name,
+                    Self::ALL.map(Self::as_str).join(", ")
+                )
+            })?;
+            if !buffers.contains(&buffer) {
+                buffers.push(buffer);
+            }
+        }
+        Ok(buffers)
+    }
+
+    pub fn list_to_string(buffers: &[Self]) -> String {
+        buffers
+            .iter()
+            .map(|b| b.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A line parsed from logcat output.
+    Log,
+    /// A line that matched no format and follows nothing it belongs to,
+    /// such as a buffer divider.
+    Unparsed,
+    /// A line that matched no format, attached to the entry before it
+    /// (wrapped native output and the like). Carries that entry's metadata.
+    Continuation,
+    /// A note inserted by colored-logcat itself, e.g. across a reconnect.
+    Marker,
+}
+
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     pub raw: String,
-    pub timestamp: String,
-    pub pid: u32,
-    pub tid: u32,
+    pub timestamp: Timestamp, // empty for formats without a time column
+    pub pid: u32,          // 0 when the format does not carry it
+    pub tid: u32,          // 0 when the format does not carry it
+    pub uid: Option<u32>,  // only with -v uid
     pub level: LogLevel,
     pub tag: String,
     pub message: String,
     pub pretty_json: Option<String>,
+    pub kind: EntryKind,
+    /// Layout the line was written in; decides which columns are meaningful.
+    pub format: LogFormat,
+    /// Serial of the device the line came from, when streaming from adb.
+    pub device: Option<Arc<str>>,
+    /// Ring buffer the line came from, taken from the preceding logcat divider.
+    pub buffer: Option<LogBuffer>,
+    /// Later lines folded into this one, e.g. the frames of a stack trace.
+    pub folded: Vec<LogEntry>,
+}
+
+static DIVIDER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // "--------- beginning of crash" / "--------- switch to main" (with -D)
+    Regex::new(r"^-{9} (?:beginning of|switch to) (\w+)").unwrap()
+});
+
+static STACK_FRAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // Java frames and causes, and native backtrace frames ("#00 pc 0004793e ...")
+    Regex::new(r"^\s*(?:at\s|Caused by:|Suppressed:|\.\.\. \d+ more|#\d{2,} pc )").unwrap()
+});
+
+/// Buffer named by a logcat divider line, if `line` is one.
+pub fn parse_divider(line: &str) -> Option<LogBuffer> {
+    let caps = DIVIDER_RE.captures(line)?;
+    LogBuffer::from_name(&caps[1])
+}
+
+/// The logcat `-v` layouts the parser understands. Time modifiers (`epoch`,
+/// `monotonic`, `usec`, `year`, `zone`) and `uid` are handled within each layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Threadtime,
+    Time,
+    Brief,
+    Process,
+    Thread,
+    Tag,
+    Long,
+}
+
+impl LogFormat {
+    pub fn has_timestamp(self) -> bool {
+        matches!(self, Self::Threadtime | Self::Time | Self::Long)
+    }
+
+    pub fn has_pid(self) -> bool {
+        !matches!(self, Self::Tag)
+    }
+
+    pub fn has_tid(self) -> bool {
+        matches!(self, Self::Threadtime | Self::Thread | Self::Long)
+    }
+
+    pub fn has_tag(self) -> bool {
+        !matches!(self, Self::Thread)
+    }
 }
 
-static LOGCAT_RE: LazyLock<Regex> = LazyLock::new(|| {
-    // Handles both "MM-DD HH:MM:SS.mmm" and "YYYY-MM-DD HH:MM:SS.mmm" formats
-    Regex::new(r"^(?:\d{4}-)?(\d{2}-\d{2}\s+\d{2}:\d{2}:\d{2}\.\d{3})\s+(\d+)\s+(\d+)\s+([VDIWEF])\s+(.+?):\s*(.*)$")
-        .unwrap()
+// "MM-DD HH:MM:SS.mmm" with optional year (-v year), µs/ns precision (-v usec)
+// and UTC offset (-v zone), or seconds since the epoch / boot (-v epoch, -v monotonic)
+const TIME: &str =
+    r"(?P<time>(?:\d{4}-)?\d{2}-\d{2}\s+\d{2}:\d{2}:\d{2}\.\d{3,9}(?:\s+[+-]\d{4})?|\d+\.\d{3,9})";
+// -v uid prints "%5s:" (user name or numeric uid) right before the pid
+const UID: &str = r"(?:(?P<uid>[\w.]+):\s*)?";
+const LEVEL: &str = r"(?P<level>[VDIWEFA])";
+
+fn format_regex(parts: &[&str]) -> Regex {
+    Regex::new(&parts.concat()).unwrap()
+}
+
+static THREADTIME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // 10-17 12:34:56.789  1234  1256 I Tag: message
+    format_regex(&[
+        r"^\s*", TIME, r"\s+", UID, r"(?P<pid>\d+)\s+(?P<tid>\d+)\s+", LEVEL,
+        r"\s+(?P<tag>.+?):\s*(?P<msg>.*)$",
+    ])
+});
+
+static TIME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // 10-17 12:34:56.789 I/Tag( 1234): message
+    format_regex(&[
+        r"^\s*", TIME, r"\s+", LEVEL, r"/(?P<tag>.*?)\(\s*", UID,
+        r"(?P<pid>\d+)\):\s?(?P<msg>.*)$",
+    ])
+});
+
+static BRIEF_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // I/Tag( 1234): message
+    format_regex(&[
+        r"^", LEVEL, r"/(?P<tag>.*?)\(\s*", UID, r"(?P<pid>\d+)\):\s?(?P<msg>.*)$",
+    ])
+});
+
+static PROCESS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // I( 1234) message  (Tag)
+    format_regex(&[
+        r"^", LEVEL, r"\(\s*", UID, r"(?P<pid>\d+)\)\s(?P<msg>.*?)\s{2}\((?P<tag>[^()]*)\)$",
+    ])
+});
+
+static THREAD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // I( 1234: 1256) message
+    format_regex(&[
+        r"^", LEVEL, r"\(\s*", UID, r"(?P<pid>\d+):\s*(?P<tid>\d+)\)\s(?P<msg>.*)$",
+    ])
+});
+
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // I/Tag     : message
+    format_regex(&[r"^", LEVEL, r"/(?P<tag>[^:(]*?)\s*:\s(?P<msg>.*)$"])
 });
 
+static LONG_HEADER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // [ 10-17 12:34:56.789  1234: 1256 I/Tag ]
+    format_regex(&[
+        r"^\[\s+", TIME, r"\s+", UID, r"(?P<pid>\d+):\s*(?P<tid>\d+)\s+", LEVEL,
+        r"/(?P<tag>.+?)\s+\]$",
+    ])
+});
+
+// Tried in order; threadtime comes first since it is what adb produces here.
+static SINGLE_LINE_FORMATS: [(LogFormat, &LazyLock<Regex>); 6] = [
+    (LogFormat::Threadtime, &THREADTIME_RE),
+    (LogFormat::Time, &TIME_RE),
+    (LogFormat::Brief, &BRIEF_RE),
+    (LogFormat::Process, &PROCESS_RE),
+    (LogFormat::Thread, &THREAD_RE),
+    (LogFormat::Tag, &TAG_RE),
+];
+
 impl LogEntry {
-    pub fn parse(line: &str) -> Option<Self> {
-        let caps = LOGCAT_RE.captures(line)?;
+    /// Parse a single logcat line in any of the one-line `-v` formats.
+    /// `long` spans several lines and goes through `LineParser` instead.
+    /// `now` is the local time a date without a year must not be after.
+    pub fn parse(line: &str, now: NaiveDateTime) -> Option<Self> {
+        SINGLE_LINE_FORMATS.iter().find_map(|(format, re)| {
+            let caps = re.captures(line)?;
+            Self::from_captures(&caps, *format, line.to_string(), &caps["msg"], now)
+        })
+    }
 
-        let level_char = caps[4].chars().next()?;
+    fn from_captures(
+        caps: &regex::Captures,
+        format: LogFormat,
+        raw: String,
+        message: &str,
+        now: NaiveDateTime,
+    ) -> Option<Self> {
+        let level_char = caps["level"].chars().next()?;
         let level = LogLevel::from_char(level_char)?;
-
-        let message = caps[6].to_string();
+        let number = |name: &str| match caps.name(name) {
+            Some(m) => m.as_str().parse().ok(),
+            None => Some(0),
+        };
 
         Some(LogEntry {
-            raw: line.to_string(),
-            timestamp: caps[1].to_string(),
-            pid: caps[2].parse().ok()?,
-            tid: caps[3].parse().ok()?,
+            raw,
+            timestamp: caps
+                .name("time")
+                .map(|m| Timestamp::parse(m.as_str(), now))
+                .unwrap_or_default(),
+            pid: number("pid")?,
+            tid: number("tid")?,
+            uid: caps.name("uid").and_then(|m| parse_uid(m.as_str())),
             level,
-            tag: caps[5].trim().to_string(),
-            pretty_json: parse_pretty_json(&message),
-            message,
+            // -v thread has no tag column
+            tag: caps.name("tag").map_or("", |m| m.as_str().trim()).to_string(),
+            pretty_json: parse_pretty_json(message),
+            message: message.to_string(),
+            kind: EntryKind::Log,
+            format,
+            device: None,
+            buffer: None,
+            folded: Vec::new(),
         })
     }
+
+    /// Raw text of this entry followed by every line folded into it.
+    pub fn raw_lines(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.raw.as_str()).chain(self.folded.iter().map(|e| e.raw.as_str()))
+    }
+
+    /// Whether `next` belongs to the same logical record as this entry: a line
+    /// no format matched, or one written by the same thread under the same tag
+    /// and level that reads like a stack frame, or that shares the timestamp
+    /// of a header announcing more lines (`...:`, `FATAL EXCEPTION: main`).
+    /// Separate calls in one millisecond stay separate records.
+    pub fn continued_by(&self, next: &LogEntry) -> bool {
+        if self.kind != EntryKind::Log || self.device != next.device {
+            return false;
+        }
+        match next.kind {
+            EntryKind::Continuation => true,
+            EntryKind::Log => {
+                next.pid == self.pid
+                    && next.tid == self.tid
+                    && next.tag == self.tag
+                    && next.level == self.level
+                    && (STACK_FRAME_RE.is_match(&next.message)
+                        || (!next.timestamp.is_empty()
+                            && next.timestamp == self.timestamp
+                            && self.is_header()))
+            }
+            _ => false,
+        }
+    }
+
+    /// A message that says more lines follow it.
+    fn is_header(&self) -> bool {
+        self.message.trim_end().ends_with(':') || self.message.starts_with("FATAL EXCEPTION")
+    }
+
+    /// A line no format matched, kept verbatim with an unknown level.
+    /// With `previous`, it inherits that entry's time, ids, tag and buffer.
+    pub fn unparsed(line: &str, previous: Option<&LogEntry>) -> Self {
+        let base = match previous {
+            Some(previous) => LogEntry {
+                kind: EntryKind::Continuation,
+                ..previous.metadata()
+            },
+            None => LogEntry {
+                raw: String::new(),
+                timestamp: Timestamp::default(),
+                pid: 0,
+                tid: 0,
+                uid: None,
+                level: LogLevel::Unknown,
+                tag: String::new(),
+                message: String::new(),
+                pretty_json: None,
+                kind: EntryKind::Unparsed,
+                format: LogFormat::Threadtime,
+                device: None,
+                buffer: None,
+                folded: Vec::new(),
+            },
+        };
+        LogEntry {
+            raw: line.to_string(),
+            level: LogLevel::Unknown,
+            message: line.to_string(),
+            ..base
+        }
+    }
+
+    /// Everything but the text, for lines that continue this entry.
+    fn metadata(&self) -> Self {
+        LogEntry {
+            raw: String::new(),
+            timestamp: self.timestamp.clone(),
+            pid: self.pid,
+            tid: self.tid,
+            uid: self.uid,
+            level: self.level,
+            tag: self.tag.clone(),
+            message: String::new(),
+            pretty_json: None,
+            kind: self.kind,
+            format: self.format,
+            device: self.device.clone(),
+            buffer: self.buffer,
+            folded: Vec::new(),
+        }
+    }
+
+    /// A divider-style entry that always stays visible, regardless of filters.
+    pub fn marker(text: &str) -> Self {
+        LogEntry {
+            raw: format!("--------- {}", text),
+            timestamp: Timestamp::now(),
+            pid: 0,
+            tid: 0,
+            uid: None,
+            level: LogLevel::Info,
+            tag: "colored-logcat".to_string(),
+            message: text.to_string(),
+            pretty_json: None,
+            kind: EntryKind::Marker,
+            format: LogFormat::Threadtime,
+            device: None,
+            buffer: None,
+            folded: Vec::new(),
+        }
+    }
 }
 
 fn parse_pretty_json(message: &str) -> Option<String> {
@@ -97,3 +452,406 @@ fn parse_pretty_json(message: &str) -> Option<String> {
     let value: Value = serde_json::from_str(trimmed).ok()?;
     serde_json::to_string_pretty(&value).ok()
 }
+
+/// What `LineParser::feed` made of one line of logcat output.
+// Almost every line is an entry, and it is moved straight on; boxing it would
+// only add an allocation per line.
+#[allow(clippy::large_enum_variant)]
+pub enum ParsedLine {
+    Entry(LogEntry),
+    /// Layout only (a `long` header or separator); nothing to show.
+    Structural,
+}
+
+/// How long `LineParser` reuses a clock reading; a year is only ever
+/// inferred to the day.
+const CLOCK_REFRESH: Duration = Duration::from_secs(1);
+
+/// Stateful line parser for a single stream: remembers the buffer named by the
+/// last divider, the header of the `long` record being read, and the last
+/// entry so that unparsed lines can continue it.
+#[derive(Default)]
+pub struct LineParser {
+    buffer: Option<LogBuffer>,
+    long_header: Option<LogEntry>,
+    previous: Option<LogEntry>,
+    divided: bool, // dividers mark every buffer switch (`-D`)
+    /// The local time, and when it was read.
+    clock: Option<(Instant, NaiveDateTime)>,
+}
+
+impl LineParser {
+    /// A parser for `logcat -D` output, where each divider names the buffer
+    /// of the lines after it.
+    pub fn divided() -> Self {
+        Self {
+            divided: true,
+            ..Self::default()
+        }
+    }
+
+    /// The local time to infer years against, read at most once a second
+    /// rather than for every line.
+    fn now(&mut self) -> NaiveDateTime {
+        match self.clock {
+            Some((read, now)) if read.elapsed() < CLOCK_REFRESH => now,
+            _ => {
+                let now = Local::now().naive_local();
+                self.clock = Some((Instant::now(), now));
+                now
+            }
+        }
+    }
+
+    pub fn feed(&mut self, line: &str) -> ParsedLine {
+        if let Some(divider) = parse_divider(line) {
+            // Without -D, "beginning of crash" only marks the first crash line;
+            // the lines after it can come from any buffer. A "switch to"
+            // divider shows the stream was written with -D after all.
+            self.divided |= line.starts_with("--------- switch to");
+            self.buffer = self.divided.then_some(divider);
+            self.long_header = None;
+            self.previous = None;
+            return ParsedLine::Entry(LogEntry {
+                buffer: Some(divider),
+                ..LogEntry::unparsed(line, None)
+            });
+        }
+
+        if let Some(caps) = LONG_HEADER_RE.captures(line) {
+            let now = self.now();
+            self.long_header =
+                LogEntry::from_captures(&caps, LogFormat::Long, String::new(), "", now);
+            if self.long_header.is_some() {
+                return ParsedLine::Structural;
+            }
+        }
+
+        // Every line up to the next header belongs to the current `long` record
+        let entry = if let Some(ref header) = self.long_header {
+            if line.trim().is_empty() {
+                return ParsedLine::Structural;
+            }
+            long_entry(header, line)
+        } else {
+            match LogEntry::parse(line, self.now()) {
+                Some(entry) => entry,
+                None => return ParsedLine::Entry(LogEntry::unparsed(line, self.previous.as_ref())),
+            }
+        };
+
+        let entry = LogEntry {
+            buffer: self.buffer,
+            ..entry
+        };
+        self.previous = Some(entry.metadata());
+        ParsedLine::Entry(entry)
+    }
+}
+
+/// One message line of a `long` record, with a threadtime-style `raw` so that
+/// copies and exports read back like any other line.
+fn long_entry(header: &LogEntry, message: &str) -> LogEntry {
+    LogEntry {
+        raw: format!(
+            "{} {:>5} {:>5} {} {:<8}: {}",
+            header.timestamp, header.pid, header.tid, header.level, header.tag, message
+        ),
+        message: message.to_string(),
+        pretty_json: parse_pretty_json(message),
+        ..header.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::TimeValue;
+
+    fn feed_all(parser: &mut LineParser, lines: &[&str]) -> Vec<LogEntry> {
+        lines
+            .iter()
+            .filter_map(|line| match parser.feed(line) {
+                ParsedLine::Entry(entry) => Some(entry),
+                ParsedLine::Structural => None,
+            })
+            .collect()
+    }
+
+    fn parse(line: &str) -> LogEntry {
+        LogEntry::parse(line, Local::now().naive_local())
+            .unwrap_or_else(|| panic!("no format matched {}", line))
+    }
+
+    /// Format, pid, tid, level, tag and message, the columns a format decides.
+    fn columns(entry: &LogEntry) -> (LogFormat, u32, u32, LogLevel, &str, &str) {
+        (
+            entry.format,
+            entry.pid,
+            entry.tid,
+            entry.level,
+            &entry.tag,
+            &entry.message,
+        )
+    }
+
+    #[test]
+    fn each_single_line_format() {
+        use LogFormat::*;
+        use LogLevel::*;
+        let cases = [
+            (
+                "10-17 12:34:56.789  1234  1256 I Tag: message",
+                (Threadtime, 1234, 1256, Info, "Tag", "message"),
+            ),
+            (
+                "10-17 12:34:56.789 W/Tag( 1234): message",
+                (Time, 1234, 0, Warn, "Tag", "message"),
+            ),
+            (
+                "E/Tag( 1234): message",
+                (Brief, 1234, 0, Error, "Tag", "message"),
+            ),
+            (
+                "D( 1234) message  (Tag)",
+                (Process, 1234, 0, Debug, "Tag", "message"),
+            ),
+            (
+                "V( 1234: 1256) message",
+                (Thread, 1234, 1256, Verbose, "", "message"),
+            ),
+            ("F/Tag     : message", (Tag, 0, 0, Fatal, "Tag", "message")),
+        ];
+        for (line, expected) in cases {
+            let entry = parse(line);
+            assert_eq!(columns(&entry), expected, "{}", line);
+            assert_eq!(
+                entry.timestamp.is_empty(),
+                !entry.format.has_timestamp(),
+                "{}",
+                line
+            );
+            assert_eq!(entry.uid, None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn tags_with_spaces_and_colons_in_messages() {
+        let entry =
+            parse("10-17 12:34:56.789  1234  1256 I Activity Manager: Start proc 42:com.app");
+        assert_eq!(entry.tag, "Activity Manager");
+        assert_eq!(entry.message, "Start proc 42:com.app");
+        assert_eq!(
+            parse("A/DEBUG( 99): #00 pc 0004793e").level,
+            LogLevel::Fatal
+        );
+    }
+
+    #[test]
+    fn time_modifiers() {
+        let value = |line: &str| parse(line).timestamp.value();
+        let wall = |line: &str| match value(line) {
+            Some(TimeValue::Wall(time)) => time,
+            other => panic!("{}: {:?}", line, other),
+        };
+        use chrono::{Datelike, Timelike};
+
+        // year
+        assert_eq!(
+            wall("2023-10-17 12:34:56.789  1234  1256 I Tag: m").year(),
+            2023
+        );
+        // usec, nsec
+        assert_eq!(
+            wall("10-17 12:34:56.789123  1234  1256 I Tag: m").nanosecond(),
+            789_123_000
+        );
+        assert_eq!(
+            wall("10-17 12:34:56.789123456 W/Tag( 1234): m").nanosecond(),
+            789_123_456
+        );
+        // zone
+        let zoned = wall("10-17 12:34:56.789 -0700  1234  1256 I Tag: m");
+        assert_eq!(zoned.offset().local_minus_utc(), -7 * 3600);
+        // epoch
+        assert_eq!(
+            wall("1697551330.789  1234  1256 I Tag: m").timestamp(),
+            1_697_551_330
+        );
+        // monotonic
+        assert_eq!(
+            value("  8675.309 I/Tag( 1234): m"),
+            Some(TimeValue::Uptime(std::time::Duration::new(
+                8675,
+                309_000_000
+            )))
+        );
+        // all at once
+        let entry = parse("2023-10-17 12:34:56.789123 +0200 u0_a85:  1234  1256 I Tag: m");
+        assert_eq!(
+            (entry.uid, entry.pid, entry.tid),
+            (Some(10_085), 1234, 1256)
+        );
+        assert_eq!(wall(&entry.raw).offset().local_minus_utc(), 2 * 3600);
+        assert_eq!(wall(&entry.raw).nanosecond(), 789_123_000);
+    }
+
+    #[test]
+    fn uid_modifier_in_every_format_with_a_pid() {
+        let cases = [
+            (
+                "10-17 12:34:56.789  1000:  1234  1256 I Tag: m",
+                LogFormat::Threadtime,
+                1000,
+            ),
+            (
+                "10-17 12:34:56.789 I/Tag(system: 1234): m",
+                LogFormat::Time,
+                1000,
+            ),
+            ("I/Tag(u10_a7: 1234): m", LogFormat::Brief, 1_010_007),
+            ("I(u0_i3: 1234) m  (Tag)", LogFormat::Process, 99_003),
+            ("I(shell: 1234: 1256) m", LogFormat::Thread, 2000),
+        ];
+        for (line, format, uid) in cases {
+            let entry = parse(line);
+            assert_eq!(
+                (entry.format, entry.uid, entry.pid),
+                (format, Some(uid), 1234),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn long_records_span_lines() {
+        let entries = feed_all(
+            &mut LineParser::default(),
+            &[
+                "[ 10-17 12:34:56.789 u0_a85:  1234: 1256 W/Tag ]",
+                "first line",
+                "second line",
+                "",
+                "[ 2023-10-17 12:34:57.000123  1234: 1257 E/Other Tag ]",
+                "boom",
+                "",
+            ],
+        );
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            columns(&entries[0]),
+            (
+                LogFormat::Long,
+                1234,
+                1256,
+                LogLevel::Warn,
+                "Tag",
+                "first line"
+            )
+        );
+        assert_eq!(entries[0].uid, Some(10_085));
+        assert_eq!(entries[1].message, "second line");
+        assert_eq!(entries[1].timestamp, entries[0].timestamp);
+        assert_eq!(
+            columns(&entries[2]),
+            (
+                LogFormat::Long,
+                1234,
+                1257,
+                LogLevel::Error,
+                "Other Tag",
+                "boom"
+            )
+        );
+        assert_eq!(entries[2].timestamp.as_str(), "2023-10-17 12:34:57.000123");
+        // Read back as threadtime
+        assert_eq!(parse(&entries[2].raw).message, "boom");
+    }
+
+    #[test]
+    fn only_continuation_like_lines_fold() {
+        let line = |time: &str, message: &str| {
+            parse(&format!("10-17 12:34:{}  1234  1256 E AndroidRuntime: {}", time, message))
+        };
+        let crash = line("56.789", "FATAL EXCEPTION: main");
+        assert!(crash.continued_by(&line("56.789", "Process: com.example, PID: 1234")));
+        assert!(crash.continued_by(&line("56.790", "\tat com.example.Main.run(Main.java:3)")));
+        assert!(crash.continued_by(&line("56.790", "Caused by: java.io.IOException")));
+
+        let header = line("56.789", "Pending requests:");
+        assert!(header.continued_by(&line("56.789", "  GET /a")));
+        assert!(!header.continued_by(&line("56.790", "  GET /a")));
+
+        // Separate calls from a loop, all in one millisecond
+        let call = line("56.789", "tick 1");
+        assert!(!call.continued_by(&line("56.789", "tick 2")));
+    }
+
+    #[test]
+    fn unmatched_lines_continue_the_previous_entry() {
+        let entries = feed_all(
+            &mut LineParser::default(),
+            &[
+                "10-17 12:34:56.789  1234  1256 E Tag: start",
+                "    wrapped native output",
+            ],
+        );
+        assert_eq!(entries[1].kind, EntryKind::Continuation);
+        assert_eq!(
+            (entries[1].pid, entries[1].level),
+            (1234, LogLevel::Unknown)
+        );
+        assert!(LogEntry::parse("not a log line", Local::now().naive_local()).is_none());
+    }
+
+    #[test]
+    fn crash_divider_without_switches_does_not_tag_later_lines() {
+        let entries = feed_all(
+            &mut LineParser::default(),
+            &[
+                "--------- beginning of crash",
+                "10-17 12:34:56.789  1234  1256 E AndroidRuntime: FATAL EXCEPTION: main",
+                "--------- beginning of main",
+                "10-17 12:34:57.000  1234  1256 I Tag: ordinary",
+                "10-17 12:34:57.001  1234  1256 D Tag: also ordinary",
+            ],
+        );
+        assert_eq!(entries[0].buffer, Some(LogBuffer::Crash));
+        for entry in &entries[3..] {
+            assert_eq!(entry.buffer, None);
+            assert!(!crate::filter::is_crash_entry(entry), "{}", entry.raw);
+        }
+    }
+
+    #[test]
+    fn divided_stream_tags_lines_with_their_buffer() {
+        let entries = feed_all(
+            &mut LineParser::divided(),
+            &[
+                "--------- beginning of crash",
+                "10-17 12:34:56.789  1234  1256 E AndroidRuntime: FATAL EXCEPTION: main",
+                "--------- switch to main",
+                "10-17 12:34:57.000  1234  1256 I Tag: ordinary",
+            ],
+        );
+        assert_eq!(entries[1].buffer, Some(LogBuffer::Crash));
+        assert_eq!(entries[3].buffer, Some(LogBuffer::Main));
+        assert!(!crate::filter::is_crash_entry(&entries[3]));
+    }
+
+    #[test]
+    fn switch_divider_marks_a_replayed_stream_as_divided() {
+        let entries = feed_all(
+            &mut LineParser::default(),
+            &[
+                "--------- switch to crash",
+                "10-17 12:34:56.789  1234  1256 I Tag: from crash",
+                "--------- beginning of system",
+                "10-17 12:34:57.000  1234  1256 I Tag: from system",
+            ],
+        );
+        assert_eq!(entries[1].buffer, Some(LogBuffer::Crash));
+        assert_eq!(entries[3].buffer, Some(LogBuffer::System));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::config;
+use crate::parser::LogLevel;
+use crate::process::is_package_name;
+use crate::query::MatchOptions;
+
+/// Presets file in the working directory, meant to be committed so a team
+/// shares the same presets.
+pub const PROJECT_FILE: &str = ".colored-logcat.json";
+
+const LEVELS: [LogLevel; 7] = [
+    LogLevel::Verbose,
+    LogLevel::Debug,
+    LogLevel::Info,
+    LogLevel::Warn,
+    LogLevel::Error,
+    LogLevel::Fatal,
+    LogLevel::Unknown,
+];
+
+/// A named set of filters, recalled from the `F` picker. Exclude lists are
+/// not part of it; they stay in effect whichever preset is applied.
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub query: String,
+    pub tag: String,
+    pub package: Option<String>,
+    pub level_toggles: [bool; 7],
+    pub match_options: MatchOptions,
+    pub shared: bool, // kept in PROJECT_FILE rather than the user's config
+}
+
+impl Preset {
+    /// Read one preset. Only the name is required, so a hand-written
+    /// `{"name": "bt", "query": "tag:Bluetooth"}` is enough. A package that is
+    /// not a package name rejects the preset; it would reach the device shell.
+    pub fn from_json(value: &Value, shared: bool) -> Option<Self> {
+        let text = |key: &str| value[key].as_str().unwrap_or_default().to_string();
+        let level_toggles = match value["levels"].as_str() {
+            Some(levels) => LEVELS.map(|level| levels.contains(level.as_char())),
+            None => [true; 7],
+        };
+        let package = match value["package"].as_str().filter(|p| !p.is_empty()) {
+            Some(package) if !is_package_name(package) => return None,
+            package => package.map(str::to_string),
+        };
+        Some(Self {
+            name: value["name"].as_str().filter(|name| !name.is_empty())?.to_string(),
+            query: text("query"),
+            tag: text("tag"),
+            package,
+            level_toggles,
+            match_options: MatchOptions {
+                smart_case: value["smart_case"].as_bool().unwrap_or(false),
+                literal: value["literal"].as_bool().unwrap_or(false),
+                whole_word: value["whole_word"].as_bool().unwrap_or(false),
+            },
+            shared,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "query": self.query,
+            "tag": self.tag,
+            "package": self.package,
+            "levels": self.levels(),
+            "smart_case": self.match_options.smart_case,
+            "literal": self.match_options.literal,
+            "whole_word": self.match_options.whole_word,
+        })
+    }
+
+    /// The enabled levels as letters, `VDIWEF?` for all.
+    fn levels(&self) -> String {
+        LEVELS
+            .iter()
+            .filter(|level| self.level_toggles[level.index()])
+            .map(|level| level.as_char())
+            .collect()
+    }
+
+    /// One-line description for the picker, in the filter bar's notation.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.query.is_empty() {
+            parts.push(format!("/{}/", self.query));
+        }
+        if !self.tag.is_empty() {
+            parts.push(format!("tag:{}", self.tag));
+        }
+        if let Some(ref package) = self.package {
+            parts.push(format!("pkg:{}", package));
+        }
+        if self.level_toggles.contains(&false) {
+            parts.push(format!("levels:{}", self.levels()));
+        }
+        if parts.is_empty() {
+            "no filters".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+}
+
+pub fn parse_list(value: &Value, shared: bool) -> Vec<Preset> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(|v| Preset::from_json(v, shared)).collect())
+        .unwrap_or_default()
+}
+
+/// Read the project's presets. A missing file has none; an unreadable one is
+/// reported.
+pub fn load_project() -> (Vec<Preset>, Option<String>) {
+    let Ok(text) = fs::read_to_string(PROJECT_FILE) else {
+        return (Vec::new(), None);
+    };
+    let value = match serde_json::from_str::<Value>(&text) {
+        Ok(value) => value,
+        Err(e) => return (Vec::new(), Some(format!("Ignoring {}: {}", PROJECT_FILE, e))),
+    };
+    let presets = parse_list(&value["presets"], true);
+    let rejected = value["presets"].as_array().map_or(0, Vec::len) - presets.len();
+    let error = (rejected > 0).then(|| {
+        format!(
+            "Ignoring {} preset(s) in {} without a name or with an invalid package",
+            rejected, PROJECT_FILE
+        )
+    });
+    (presets, error)
+}
+
+/// Write the shared presets to the project file, keeping anything else in it.
+/// A file that does not parse is the team's to fix, so it is not replaced.
+pub fn save_project(presets: &[Preset]) -> Result<(), String> {
+    let path = Path::new(PROJECT_FILE);
+    let mut root = config::read_object(path)?;
+    root.insert(
+        "presets".to_string(),
+        presets.iter().map(Preset::to_json).collect(),
+    );
+    config::write_object(path, root)
+}
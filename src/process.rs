@@ -0,0 +1,142 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use crate::parser::LogEntry;
+
+static START_PROC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // "Start proc 12345:com.example.app/u0a85 for activity {...}" (Android 7+)
+    // "Start proc com.example.app for activity ...: pid=12345 uid=10085 ..." (older)
+    Regex::new(
+        r"^Start proc (?:(?P<pid>\d+):(?P<name>[^\s/]+)/|(?P<old_name>\S+) for .*?\bpid=(?P<old_pid>\d+))",
+    )
+    .unwrap()
+});
+
+static AM_PROC_START_RE: LazyLock<Regex> = LazyLock::new(|| {
+    // events buffer: "am_proc_start: [0,12345,10085,com.example.app,activity,...]"
+    Regex::new(r"^\[\d+,(?P<pid>\d+),\d+,(?P<name>[^,\]]+),").unwrap()
+});
+
+static PACKAGE_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9._:]+$").unwrap());
+
+type NamesById = HashMap<Option<Arc<str>>, HashMap<u32, String>>;
+
+/// Process and thread names by device and id, from `ps -A` snapshots,
+/// `Start proc` lines and `/proc/<pid>/task/*/comm`. Names of exited
+/// processes are kept for the lines they left behind.
+#[derive(Debug, Default)]
+pub struct ProcessTable {
+    names: NamesById,
+    threads: NamesById,
+}
+
+impl ProcessTable {
+    /// The name of the process that logged `entry`, if known.
+    pub fn name(&self, entry: &LogEntry) -> Option<&str> {
+        self.names.get(&entry.device)?.get(&entry.pid).map(String::as_str)
+    }
+
+    /// The name of the thread that logged `entry`. A process's first thread
+    /// (tid == pid) is its main thread, whatever `comm` calls it.
+    pub fn thread_name(&self, entry: &LogEntry) -> Option<&str> {
+        if entry.tid == entry.pid && entry.format.has_tid() {
+            return Some("main");
+        }
+        self.threads.get(&entry.device)?.get(&entry.tid).map(String::as_str)
+    }
+
+    /// Record a process; returns whether the table changed.
+    pub fn insert(&mut self, device: Option<Arc<str>>, pid: u32, name: &str) -> bool {
+        insert_name(&mut self.names, device, pid, name)
+    }
+
+    /// Every known process name, on any device.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.values().flat_map(|names| names.values().map(String::as_str))
+    }
+
+    pub fn insert_thread(&mut self, device: Option<Arc<str>>, tid: u32, name: &str) {
+        insert_name(&mut self.threads, device, tid, name);
+    }
+}
+
+fn insert_name(table: &mut NamesById, device: Option<Arc<str>>, id: u32, name: &str) -> bool {
+    let names = table.entry(device).or_default();
+    if names.get(&id).is_some_and(|known| known == name) {
+        return false;
+    }
+    names.insert(id, name.to_string());
+    true
+}
+
+/// The PID and process name announced by an `ActivityManager: Start proc`
+/// line (or the `am_proc_start` event), if `entry` is one.
+pub fn started_process(entry: &LogEntry) -> Option<(u32, &str)> {
+    let caps = match entry.tag.as_str() {
+        "ActivityManager" => START_PROC_RE.captures(&entry.message)?,
+        "am_proc_start" => AM_PROC_START_RE.captures(&entry.message)?,
+        _ => return None,
+    };
+    let pid = caps.name("pid").or_else(|| caps.name("old_pid"))?;
+    let name = caps.name("name").or_else(|| caps.name("old_name"))?;
+    Some((pid.as_str().parse().ok()?, name.as_str()))
+}
+
+/// The app's main process (`com.example.app`) or one of its subprocesses
+/// (`com.example.app:remote`).
+pub fn belongs_to_package(process: &str, package: &str) -> bool {
+    process
+        .strip_prefix(package)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
+}
+
+/// Whether `name` can be a package or process name. Package names are sent
+/// to the device shell, so nothing else is let through.
+pub fn is_package_name(name: &str) -> bool {
+    PACKAGE_NAME_RE.is_match(name)
+}
+
+/// UIDs per Android user; user 10's apps run as 10xxxxx.
+pub const PER_USER_RANGE: u32 = 100_000;
+const FIRST_APPLICATION_UID: u32 = 10_000;
+const FIRST_ISOLATED_UID: u32 = 99_000;
+
+/// Well-known system UIDs by the names `-v uid` prints for them.
+const SYSTEM_UIDS: [(&str, u32); 14] = [
+    ("root", 0),
+    ("system", 1000),
+    ("radio", 1001),
+    ("bluetooth", 1002),
+    ("graphics", 1003),
+    ("input", 1004),
+    ("audio", 1005),
+    ("camera", 1006),
+    ("log", 1007),
+    ("wifi", 1010),
+    ("media", 1013),
+    ("nfc", 1027),
+    ("shell", 2000),
+    ("nobody", 9999),
+];
+
+/// A UID as `-v uid` prints it: a number, an app name (`u0_a85`, `u10_i3` for
+/// isolated processes), or a system name (`system`, `u10_system`).
+pub fn parse_uid(text: &str) -> Option<u32> {
+    if let Ok(uid) = text.parse() {
+        return Some(uid);
+    }
+    let (user, name) = match text.strip_prefix('u').and_then(|rest| rest.split_once('_')) {
+        Some((user, name)) => (user.parse::<u32>().ok()?, name),
+        None => (0, text),
+    };
+    let app_id = if let Some(n) = name.strip_prefix('a').and_then(|n| n.parse::<u32>().ok()) {
+        FIRST_APPLICATION_UID + n
+    } else if let Some(n) = name.strip_prefix('i').and_then(|n| n.parse::<u32>().ok()) {
+        FIRST_ISOLATED_UID + n
+    } else {
+        SYSTEM_UIDS.iter().find(|(known, _)| *known == name)?.1
+    };
+    Some(user * PER_USER_RANGE + app_id)
+}
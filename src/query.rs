@@ -0,0 +1,736 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::parser::{LogEntry, LogLevel};
+use crate::process::{parse_uid, ProcessTable, PER_USER_RANGE};
+use crate::timestamp::{parse_duration, TimePoint, TimeValue};
+
+/// A filter typed into the `/` prompt, e.g.
+/// `level>=W tag:OkHttp|Retrofit -tag:chatty msg~"timeout" pid:1234`.
+///
+/// Terms next to each other are ANDed; `or` (or `|`) between terms, `-`/`not`
+/// in front of one and parentheses work as usual. A bare word is a regex
+/// matched against the message or tag, as the prompt always did.
+pub struct Query {
+    pub source: String,
+    expr: Expr,
+    resolved_at: Option<TimeValue>, // the "now" `last:` terms count back from
+}
+
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Pred(Predicate),
+}
+
+enum Predicate {
+    /// A bare word: regex over message or tag.
+    Text(Regex),
+    Level(Comparison, LogLevel),
+    Levels(Vec<LogLevel>),
+    Tag(TextMatch),
+    Message(TextMatch),
+    Device(TextMatch),
+    /// Process name, resolved through the process table.
+    Process(TextMatch),
+    Pid(Vec<u32>),
+    Tid(Vec<u32>),
+    Uid(Vec<u32>),
+    /// Android user (profile) id, derived from the uid.
+    User(Vec<u32>),
+    /// At or after this time; lines without a comparable time never match.
+    After(TimePoint),
+    /// At or before this time.
+    Before(TimePoint),
+    /// Within this long of the newest line, until `resolve_last` turns it
+    /// into `After`.
+    Last(Duration),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+enum TextMatch {
+    /// `field:a|b` — contains any alternative.
+    Contains(Vec<String>),
+    /// `field=a|b` — equals any alternative.
+    Equals(Vec<String>),
+    /// `field~regex`
+    Regex(Regex),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Level,
+    Tag,
+    Message,
+    Device,
+    Process,
+    Pid,
+    Tid,
+    Uid,
+    User,
+    After,
+    Before,
+    Last,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "level" | "lvl" => Some(Self::Level),
+            "tag" => Some(Self::Tag),
+            "msg" | "message" => Some(Self::Message),
+            "dev" | "device" => Some(Self::Device),
+            "proc" | "process" => Some(Self::Process),
+            "pid" => Some(Self::Pid),
+            "tid" => Some(Self::Tid),
+            "uid" => Some(Self::Uid),
+            "user" => Some(Self::User),
+            "after" | "since" => Some(Self::After),
+            "before" | "until" => Some(Self::Before),
+            "last" => Some(Self::Last),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Colon,
+    Equals,
+    Tilde,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+// Longest first so ">=" is not read as ">"
+const OPS: [(&str, Op); 7] = [
+    (">=", Op::GreaterEq),
+    ("<=", Op::LessEq),
+    (":", Op::Colon),
+    ("=", Op::Equals),
+    ("~", Op::Tilde),
+    (">", Op::Greater),
+    ("<", Op::Less),
+];
+
+struct Term {
+    field: Option<(Field, Op)>,
+    value: String,
+    pos: usize,
+}
+
+enum Token {
+    Open(usize),
+    Close(usize),
+    Not,
+    And,
+    Or,
+    Term(Term),
+}
+
+/// How typed patterns are read: bare query words, `~` values and the tag
+/// filter. Toggled with Alt+C / Alt+R / Alt+W.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchOptions {
+    /// Ignore case unless the pattern has an uppercase letter, like ripgrep.
+    pub smart_case: bool,
+    /// Match the text as typed; `.`, `?` and friends need no escaping.
+    pub literal: bool,
+    /// Only match at word boundaries.
+    pub whole_word: bool,
+}
+
+impl MatchOptions {
+    pub fn compile(&self, pattern: &str) -> Result<Regex, String> {
+        let mut body = if self.literal {
+            regex::escape(pattern)
+        } else {
+            // Report errors against the pattern as typed, not the wrapped one
+            compile_regex(pattern)?;
+            pattern.to_string()
+        };
+        if self.whole_word {
+            body = format!(r"\b(?:{})\b", body);
+        }
+        if self.smart_case && !has_uppercase(pattern, self.literal) {
+            body = format!("(?i){}", body);
+        }
+        compile_regex(&body)
+    }
+}
+
+/// Uppercase letters in the pattern, not counting regex escapes like `\S`.
+fn has_uppercase(pattern: &str, literal: bool) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' && !literal {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+impl Query {
+    /// Parse a query. Errors name the 1-based column they were found at.
+    pub fn parse(source: &str, options: &MatchOptions) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?.into_iter().peekable(),
+            options: *options,
+        };
+        let expr = parser.parse_or()?;
+        match parser.tokens.next() {
+            None => Ok(Self {
+                source: source.to_string(),
+                expr,
+                resolved_at: None,
+            }),
+            Some(Token::Close(pos)) => Err(format!("Unmatched ')' at column {}", pos + 1)),
+            Some(_) => Err("Unexpected input after query".to_string()),
+        }
+    }
+
+    pub fn matches(&self, entry: &LogEntry, processes: &ProcessTable) -> bool {
+        self.expr.matches(entry, processes)
+    }
+
+    /// Whether the result can change as process names become known.
+    pub fn uses_process_names(&self) -> bool {
+        self.expr.any_pred(&|pred| matches!(pred, Predicate::Process(_)))
+    }
+
+    /// Pin `last:` terms to a moment, normally the newest line's time.
+    pub fn resolve_last(&mut self, anchor: TimeValue) {
+        self.expr.resolve_last(anchor);
+        self.resolved_at = Some(anchor);
+    }
+
+    pub fn resolved_at(&self) -> Option<TimeValue> {
+        self.resolved_at
+    }
+
+    /// Whether the query needs the uid column (`logcat -v uid`).
+    pub fn uses_uid(&self) -> bool {
+        self.expr
+            .any_pred(&|pred| matches!(pred, Predicate::Uid(_) | Predicate::User(_)))
+    }
+}
+
+impl Expr {
+    fn matches(&self, entry: &LogEntry, processes: &ProcessTable) -> bool {
+        match self {
+            Self::And(exprs) => exprs.iter().all(|e| e.matches(entry, processes)),
+            Self::Or(exprs) => exprs.iter().any(|e| e.matches(entry, processes)),
+            Self::Not(expr) => !expr.matches(entry, processes),
+            Self::Pred(pred) => pred.matches(entry, processes),
+        }
+    }
+
+    fn resolve_last(&mut self, anchor: TimeValue) {
+        match self {
+            Self::And(exprs) | Self::Or(exprs) => {
+                exprs.iter_mut().for_each(|e| e.resolve_last(anchor))
+            }
+            Self::Not(expr) => expr.resolve_last(anchor),
+            Self::Pred(pred) => {
+                if let Predicate::Last(duration) = *pred {
+                    *pred = Predicate::After(TimePoint::At(anchor.before(duration)));
+                }
+            }
+        }
+    }
+
+    fn any_pred(&self, f: &dyn Fn(&Predicate) -> bool) -> bool {
+        match self {
+            Self::And(exprs) | Self::Or(exprs) => exprs.iter().any(|e| e.any_pred(f)),
+            Self::Not(expr) => expr.any_pred(f),
+            Self::Pred(pred) => f(pred),
+        }
+    }
+}
+
+impl Predicate {
+    fn matches(&self, entry: &LogEntry, processes: &ProcessTable) -> bool {
+        // Folded lines (stack frames) match on behalf of the whole record
+        let any_message = |f: &dyn Fn(&str) -> bool| {
+            f(&entry.message) || entry.folded.iter().any(|e| f(&e.message))
+        };
+        match self {
+            Self::Text(re) => any_message(&|m| re.is_match(m)) || re.is_match(&entry.tag),
+            Self::Level(cmp, level) => match cmp {
+                Comparison::Less => entry.level < *level,
+                Comparison::LessEq => entry.level <= *level,
+                Comparison::Greater => entry.level > *level,
+                Comparison::GreaterEq => entry.level >= *level,
+            },
+            Self::Levels(levels) => levels.contains(&entry.level),
+            Self::Tag(m) => m.matches(&entry.tag),
+            Self::Message(m) => any_message(&|text| m.matches(text)),
+            Self::Device(m) => entry.device.as_deref().is_some_and(|d| m.matches(d)),
+            Self::Process(m) => processes.name(entry).is_some_and(|name| m.matches(name)),
+            Self::Pid(pids) => pids.contains(&entry.pid),
+            Self::Tid(tids) => tids.contains(&entry.tid),
+            Self::Uid(uids) => entry.uid.is_some_and(|uid| uids.contains(&uid)),
+            Self::User(users) => entry
+                .uid
+                .is_some_and(|uid| users.contains(&(uid / PER_USER_RANGE))),
+            Self::After(point) => point.cmp_timestamp(&entry.timestamp).is_some_and(Ordering::is_ge),
+            Self::Before(point) => point.cmp_timestamp(&entry.timestamp).is_some_and(Ordering::is_le),
+            Self::Last(_) => true,
+        }
+    }
+}
+
+impl TextMatch {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Self::Contains(needles) => needles.iter().any(|n| text.contains(n.as_str())),
+            Self::Equals(values) => values.iter().any(|v| v == text),
+            Self::Regex(re) => re.is_match(text),
+        }
+    }
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    options: MatchOptions,
+}
+
+impl Parser {
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.parse_and()?];
+        while self.tokens.next_if(|t| matches!(t, Token::Or)).is_some() {
+            terms.push(self.parse_and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Or(terms)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.parse_unary()?];
+        loop {
+            match self.tokens.peek() {
+                Some(Token::And) => {
+                    self.tokens.next();
+                }
+                Some(Token::Not | Token::Open(_) | Token::Term(_)) => {}
+                _ => break,
+            }
+            terms.push(self.parse_unary()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::And(terms)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.tokens.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open(pos)) => {
+                let expr = self.parse_or()?;
+                match self.tokens.next() {
+                    Some(Token::Close(_)) => Ok(expr),
+                    _ => Err(format!("Unclosed '(' at column {}", pos + 1)),
+                }
+            }
+            Some(Token::Term(term)) => Ok(Expr::Pred(predicate(term, &self.options)?)),
+            Some(Token::Close(pos)) => Err(format!("Unexpected ')' at column {}", pos + 1)),
+            Some(Token::And | Token::Or) => Err("Expected a term before and/or".to_string()),
+            None => Err("Expected a term".to_string()),
+        }
+    }
+}
+
+fn predicate(term: Term, options: &MatchOptions) -> Result<Predicate, String> {
+    let at = |msg: String| format!("{} at column {}", msg, term.pos + 1);
+    let Some((field, op)) = term.field else {
+        return options.compile(&term.value).map(Predicate::Text).map_err(at);
+    };
+    if term.value.is_empty() {
+        return Err(at("Missing value".to_string()));
+    }
+    let alternatives = || term.value.split('|').map(str::to_string).collect::<Vec<_>>();
+
+    match (field, op) {
+        (Field::Level, Op::Colon | Op::Equals) => term
+            .value
+            .split('|')
+            .map(parse_level)
+            .collect::<Result<_, _>>()
+            .map(Predicate::Levels)
+            .map_err(at),
+        (Field::Level, Op::Tilde) => Err(at("level takes : = < <= > >=".to_string())),
+        (Field::Level, cmp) => {
+            let cmp = match cmp {
+                Op::Less => Comparison::Less,
+                Op::LessEq => Comparison::LessEq,
+                Op::Greater => Comparison::Greater,
+                _ => Comparison::GreaterEq,
+            };
+            parse_level(&term.value).map(|l| Predicate::Level(cmp, l)).map_err(at)
+        }
+        (Field::Pid | Field::Tid, Op::Colon | Op::Equals) => {
+            let numbers = term
+                .value
+                .split('|')
+                .map(|n| n.parse().map_err(|_| format!("'{}' is not a number", n)))
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(at)?;
+            Ok(match field {
+                Field::Pid => Predicate::Pid(numbers),
+                _ => Predicate::Tid(numbers),
+            })
+        }
+        (Field::Pid | Field::Tid, _) => Err(at("pid and tid take : or =".to_string())),
+        (Field::Uid, Op::Colon | Op::Equals) => term
+            .value
+            .split('|')
+            .map(|uid| parse_uid(uid).ok_or_else(|| format!("'{}' is not a uid", uid)))
+            .collect::<Result<_, _>>()
+            .map(Predicate::Uid)
+            .map_err(at),
+        (Field::User, Op::Colon | Op::Equals) => term
+            .value
+            .split('|')
+            .map(|n| n.parse().map_err(|_| format!("'{}' is not a user id", n)))
+            .collect::<Result<_, _>>()
+            .map(Predicate::User)
+            .map_err(at),
+        (Field::Uid | Field::User, _) => Err(at("uid and user take : or =".to_string())),
+        (Field::After, Op::Colon) => TimePoint::parse(&term.value).map(Predicate::After).map_err(at),
+        (Field::Before, Op::Colon) => {
+            TimePoint::parse(&term.value).map(Predicate::Before).map_err(at)
+        }
+        (Field::Last, Op::Colon) => parse_duration(&term.value).map(Predicate::Last).map_err(at),
+        (Field::After | Field::Before | Field::Last, _) => {
+            Err(at("after, before and last take :".to_string()))
+        }
+        (_, Op::Colon | Op::Equals | Op::Tilde) => {
+            let text = match op {
+                Op::Colon => TextMatch::Contains(alternatives()),
+                Op::Equals => TextMatch::Equals(alternatives()),
+                _ => TextMatch::Regex(options.compile(&term.value).map_err(at)?),
+            };
+            Ok(match field {
+                Field::Tag => Predicate::Tag(text),
+                Field::Message => Predicate::Message(text),
+                Field::Process => Predicate::Process(text),
+                _ => Predicate::Device(text),
+            })
+        }
+        _ => Err(at("Only level can be compared with < or >".to_string())),
+    }
+}
+
+/// Compile a regex, boiling the multi-line syntax diagnostic down to its
+/// last line so it fits in the filter bar.
+pub fn compile_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| match e {
+        regex::Error::Syntax(ref text) => {
+            let reason = text
+                .lines()
+                .last()
+                .and_then(|line| line.strip_prefix("error: "))
+                .unwrap_or("syntax error");
+            format!("Invalid regex '{}': {}", pattern, reason)
+        }
+        e => e.to_string(),
+    })
+}
+
+fn parse_level(text: &str) -> Result<LogLevel, String> {
+    let level = match text.to_ascii_lowercase().as_str() {
+        "v" | "verbose" => LogLevel::Verbose,
+        "d" | "debug" => LogLevel::Debug,
+        "i" | "info" => LogLevel::Info,
+        "w" | "warn" | "warning" => LogLevel::Warn,
+        "e" | "error" => LogLevel::Error,
+        "f" | "a" | "fatal" | "assert" => LogLevel::Fatal,
+        "?" | "unknown" => LogLevel::Unknown,
+        _ => return Err(format!("Unknown level '{}'", text)),
+    };
+    Ok(level)
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(pos, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open(pos));
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close(pos));
+            }
+            '-' | '!' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '"' => {
+                chars.next();
+                let value = quoted(&mut chars, pos)?;
+                tokens.push(Token::Term(Term {
+                    field: None,
+                    value,
+                    pos,
+                }));
+            }
+            _ => {
+                let word = word(&mut chars);
+                match word.as_str() {
+                    "and" | "AND" | "&&" => tokens.push(Token::And),
+                    "or" | "OR" | "|" | "||" => tokens.push(Token::Or),
+                    "not" | "NOT" => tokens.push(Token::Not),
+                    _ => tokens.push(Token::Term(field_term(word, pos, &mut chars)?)),
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+/// Read up to whitespace or an unbalanced ')', so regexes like `foo(bar)?`
+/// stay one word while `(a or b)` still closes its group.
+fn word(chars: &mut Chars) -> String {
+    let mut word = String::new();
+    let mut depth = 0;
+    while let Some(&(_, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => break,
+            '(' => depth += 1,
+            ')' if depth == 0 => break,
+            ')' => depth -= 1,
+            // A quoted value right after an operator: msg~"read timeout"
+            '"' if word.ends_with([':', '=', '~', '<', '>']) => break,
+            _ => {}
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+fn quoted(chars: &mut Chars, start: usize) -> Result<String, String> {
+    let mut value = String::new();
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '"' => return Ok(value),
+            '\\' => match chars.next() {
+                Some((_, '"')) => value.push('"'),
+                Some((_, c)) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    Err(format!("Unclosed '\"' at column {}", start + 1))
+}
+
+/// Split `field<op>value` when the prefix names a field; anything else is a
+/// bare regex, so patterns like `https?://` keep working.
+fn field_term(word: String, pos: usize, chars: &mut Chars) -> Result<Term, String> {
+    let split = word
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .and_then(|i| Field::from_name(&word[..i]).map(|field| (field, &word[i..])))
+        .and_then(|(field, rest)| {
+            OPS.iter()
+                .find(|(text, _)| rest.starts_with(text))
+                .map(|&(text, op)| (field, op, rest[text.len()..].to_string()))
+        });
+    let Some((field, op, mut value)) = split else {
+        return Ok(Term {
+            field: None,
+            value: word,
+            pos,
+        });
+    };
+
+    if value.is_empty() && matches!(chars.peek(), Some((_, '"'))) {
+        let (start, _) = chars.next().unwrap_or_default();
+        value = quoted(chars, start)?;
+    }
+    Ok(Term {
+        field: Some((field, op)),
+        value,
+        pos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::Timestamp;
+
+    fn entry(level: char, tag: &str, message: &str) -> LogEntry {
+        let line = format!(
+            "10-17 12:34:56.789  1234  1256 {} {}: {}",
+            level, tag, message
+        );
+        LogEntry::parse(&line, chrono::Local::now().naive_local()).unwrap()
+    }
+
+    fn matches(query: &str, entry: &LogEntry) -> bool {
+        Query::parse(query, &MatchOptions::default())
+            .unwrap_or_else(|e| panic!("{}: {}", query, e))
+            .matches(entry, &ProcessTable::default())
+    }
+
+    fn error(query: &str) -> String {
+        match Query::parse(query, &MatchOptions::default()) {
+            Ok(_) => panic!("{} parsed", query),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let query = "tag:Net msg:timeout or tag:Db";
+        assert!(matches(query, &entry('I', "Net", "read timeout")));
+        assert!(matches(query, &entry('I', "Db", "open")));
+        assert!(!matches(query, &entry('I', "Net", "connected")));
+
+        let grouped = "tag:Net (msg:timeout or msg:refused)";
+        assert!(matches(grouped, &entry('I', "Net", "connection refused")));
+        assert!(!matches(grouped, &entry('I', "Db", "connection refused")));
+    }
+
+    #[test]
+    fn and_and_or_have_spelled_out_forms() {
+        let e = entry('W', "Net", "read timeout");
+        assert!(matches("tag:Net and level>=W", &e));
+        assert!(matches("tag:Net && level=W", &e));
+        assert!(matches("tag:Db || tag:Net", &e));
+        assert!(matches("tag:Db | tag:Net", &e));
+        assert!(!matches("tag:Db OR level<W", &e));
+    }
+
+    #[test]
+    fn negation_applies_to_the_next_term_or_group() {
+        let chatty = entry('I', "chatty", "uid=1000 expire 3 lines");
+        let net = entry('I', "Net", "read timeout");
+        assert!(!matches("-tag:chatty", &chatty));
+        assert!(matches("-tag:chatty", &net));
+        assert!(matches("not tag:chatty", &net));
+        assert!(matches("!tag:chatty", &net));
+        assert!(matches("not not tag:chatty", &chatty));
+        assert!(!matches("-(tag:chatty or msg:timeout)", &net));
+        assert!(matches("-tag:chatty msg:timeout", &net));
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces_and_escaped_quotes() {
+        let e = entry('E', "Net", r#"read timeout after "30s""#);
+        assert!(matches(r#"msg~"read timeout""#, &e));
+        assert!(matches(r#"msg:"timeout after""#, &e));
+        assert!(matches(r#""read timeout""#, &e));
+        assert!(matches(r#""after \"30s\"""#, &e));
+        assert!(!matches(r#""timeout read""#, &e));
+    }
+
+    #[test]
+    fn bare_words_stay_regexes() {
+        let e = entry('I', "Http", "GET https://example.com");
+        assert!(matches("https?://", &e));
+        assert!(matches("GET (https|http)", &e));
+        assert!(matches("Ht+p", &e));
+    }
+
+    #[test]
+    fn field_operators() {
+        let e = entry('W', "OkHttp", "timeout");
+        assert!(matches("tag:Http", &e));
+        assert!(!matches("tag=Http", &e));
+        assert!(matches("tag=Retrofit|OkHttp", &e));
+        assert!(matches("tag~^Ok", &e));
+        assert!(matches("level:W|E", &e));
+        assert!(matches("level>I level<E", &e));
+        assert!(matches("pid:1234 tid=1256", &e));
+        assert!(!matches("pid:1", &e));
+    }
+
+    #[test]
+    fn time_ranges() {
+        let e = entry('I', "Tag", "message"); // 10-17 12:34:56.789
+        assert!(matches("after:12:34", &e));
+        assert!(!matches("after:12:35", &e));
+        assert!(matches("after:12:00 before:13:00", &e));
+        assert!(matches(r#"before:"10-17 12:35""#, &e));
+        assert!(!matches(r#"before:"10-17 12:34:56""#, &e));
+        assert!(matches(r#"since:"10-17 12:34:56.789""#, &e));
+        assert_eq!(
+            error("after=12:00"),
+            "after, before and last take : at column 1"
+        );
+        assert!(error("after:noon").starts_with("'noon' is not a time"));
+    }
+
+    #[test]
+    fn last_counts_back_from_the_resolved_anchor() {
+        let e = entry('I', "Tag", "message");
+        let query = |anchor: &str| {
+            let mut query = Query::parse("last:30s", &MatchOptions::default()).unwrap();
+            let now = chrono::Local::now().naive_local();
+            query.resolve_last(Timestamp::parse(anchor, now).value().unwrap());
+            query.matches(&e, &ProcessTable::default())
+        };
+        assert!(query("10-17 12:35:20.000"));
+        assert!(!query("10-17 12:35:30.000"));
+        // Until resolved, `last:` lets everything through
+        assert!(matches("last:1s", &e));
+    }
+
+    #[test]
+    fn malformed_queries_are_reported_with_their_column() {
+        assert_eq!(error(r#"msg:"open"#), "Unclosed '\"' at column 5");
+        assert_eq!(error("tag:a)"), "Unmatched ')' at column 6");
+        assert_eq!(error("(tag:a"), "Unclosed '(' at column 1");
+        assert_eq!(error("tag:"), "Missing value at column 1");
+        assert_eq!(error("x level:loud"), "Unknown level 'loud' at column 3");
+        assert_eq!(error("pid:abc"), "'abc' is not a number at column 1");
+        assert_eq!(
+            error("tag>3"),
+            "Only level can be compared with < or > at column 1"
+        );
+        assert_eq!(error("level~W"), "level takes : = < <= > >= at column 1");
+        assert!(error("a(").starts_with("Invalid regex 'a('"));
+        assert!(error("last:5x").starts_with("'5x' is not a duration"));
+    }
+
+    #[test]
+    fn dangling_operators_are_errors() {
+        assert_eq!(error("or tag:a"), "Expected a term before and/or");
+        assert_eq!(error("tag:a and"), "Expected a term");
+        assert_eq!(error("-"), "Expected a term");
+        assert_eq!(error(""), "Expected a term");
+    }
+}
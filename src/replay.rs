@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::adb::{forward_lines, AdbMessage};
+use crate::parser::LineParser;
+
+/// Stream a saved logcat file through the same channel as a live adb session.
+pub fn spawn_file(path: &Path, tx: mpsc::Sender<AdbMessage>) -> Result<(), String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let name = path.display().to_string();
+
+    thread::spawn(move || {
+        let reader = BufReader::new(file);
+        let stopped = AtomicBool::new(false);
+        let result = forward_lines(reader, LineParser::default(), &tx, None, &stopped);
+        let _ = tx.send(AdbMessage::Disconnected(None, finished_message(&name, result)));
+    });
+
+    Ok(())
+}
+
+/// Stream logcat output piped into stdin, e.g. `adb logcat -d | colored-logcat -f -`.
+pub fn spawn_stdin(tx: mpsc::Sender<AdbMessage>) {
+    thread::spawn(move || {
+        let stopped = AtomicBool::new(false);
+        let result = forward_lines(io::stdin().lock(), LineParser::default(), &tx, None, &stopped);
+        let _ = tx.send(AdbMessage::Disconnected(None, finished_message("stdin", result)));
+    });
+}
+
+fn finished_message(name: &str, result: io::Result<()>) -> String {
+    match result {
+        Ok(()) => format!("Finished reading {}", name),
+        Err(e) => format!("Read error in {}: {}", name, e),
+    }
+}
@@ -0,0 +1,391 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+
+/// Seconds values at or above this are `-v epoch` times; smaller ones are
+/// `-v monotonic` (time since boot). 10^9 s after the epoch is September 2001.
+const EPOCH_THRESHOLD: u64 = 1_000_000_000;
+
+/// What a logcat time column means once parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeValue {
+    /// A point in wall-clock time, in the device's offset when it printed one.
+    Wall(DateTime<FixedOffset>),
+    /// Time since the device booted (`-v monotonic`).
+    Uptime(Duration),
+}
+
+/// A logcat timestamp: the text exactly as printed, plus its parsed value.
+/// The value is `None` for formats without a time column or text that does
+/// not parse.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Timestamp {
+    text: String,
+    value: Option<TimeValue>,
+}
+
+impl Timestamp {
+    /// Parse any logcat time column: `MM-DD HH:MM:SS.mmm` with the optional
+    /// year (`-v year`), µs/ns precision (`-v usec`, `-v nsec`) and UTC offset
+    /// (`-v zone`), or `seconds.fraction` (`-v epoch`, `-v monotonic`).
+    /// `now` is the local time a date without a year must not be after.
+    pub fn parse(text: &str, now: NaiveDateTime) -> Self {
+        Self {
+            text: text.to_string(),
+            value: parse_value(text, now),
+        }
+    }
+
+    pub fn now() -> Self {
+        let now = Local::now();
+        Self {
+            text: now.format("%m-%d %H:%M:%S%.3f").to_string(),
+            value: Some(TimeValue::Wall(now.fixed_offset())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn value(&self) -> Option<TimeValue> {
+        self.value
+    }
+
+    /// Chronological order where both values are comparable, otherwise the
+    /// text order (which is chronological within a single format).
+    pub fn cmp_time(&self, other: &Self) -> Ordering {
+        match (self.value, other.value) {
+            (Some(TimeValue::Wall(a)), Some(TimeValue::Wall(b))) => a.cmp(&b),
+            (Some(TimeValue::Uptime(a)), Some(TimeValue::Uptime(b))) => a.cmp(&b),
+            _ => self.text.cmp(&other.text),
+        }
+    }
+}
+
+impl TimeValue {
+    pub fn now() -> Self {
+        Self::Wall(Local::now().fixed_offset())
+    }
+
+    /// `duration` earlier; uptimes stop at boot.
+    pub fn before(self, duration: Duration) -> Self {
+        match self {
+            Self::Wall(time) => Self::Wall(
+                chrono::Duration::from_std(duration)
+                    .ok()
+                    .and_then(|d| time.checked_sub_signed(d))
+                    .unwrap_or(time),
+            ),
+            Self::Uptime(uptime) => Self::Uptime(uptime.saturating_sub(duration)),
+        }
+    }
+}
+
+/// A moment typed by the user, for time filters and goto-time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimePoint {
+    /// A full timestamp in any form logcat prints, seconds optional
+    /// (`10-17 14:02:10`, `2023-10-17 14:02:10.500`, `1697551330.5`).
+    At(TimeValue),
+    /// `14:02:10` or `14:02`: that time of day, on whatever date a line has.
+    TimeOfDay(NaiveTime),
+}
+
+impl TimePoint {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        for format in ["%H:%M:%S%.f", "%H:%M"] {
+            if let Ok(time) = NaiveTime::parse_from_str(text, format) {
+                return Ok(Self::TimeOfDay(time));
+            }
+        }
+        // logcat always prints seconds and a fraction; people quoting a time
+        // often don't
+        let mut parts: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+        if let Some(clock) = parts.get_mut(1) {
+            if clock.matches(':').count() == 1 {
+                clock.push_str(":00");
+            }
+            if !clock.contains('.') {
+                clock.push_str(".0");
+            }
+        }
+        parse_value(&parts.join(" "), Local::now().naive_local())
+            .map(Self::At)
+            .ok_or_else(|| format!("'{}' is not a time (try 14:02:10 or 10-17 14:02:10)", text))
+    }
+
+    /// Where `timestamp` falls relative to this point, or `None` when the two
+    /// cannot be compared (no time column, or uptime against wall-clock time).
+    pub fn cmp_timestamp(&self, timestamp: &Timestamp) -> Option<Ordering> {
+        match (timestamp.value?, self) {
+            (TimeValue::Wall(a), Self::At(TimeValue::Wall(b))) => Some(a.cmp(b)),
+            (TimeValue::Uptime(a), Self::At(TimeValue::Uptime(b))) => Some(a.cmp(b)),
+            (TimeValue::Wall(a), Self::TimeOfDay(b)) => Some(a.time().cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+/// `30s`, `5m`, `1h30m`, `500ms`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("'{}' is not a duration (try 30s, 5m or 1h)", text);
+    let mut total = Duration::ZERO;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let amount: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        total += match &rest[..unit] {
+            "ms" => Duration::from_millis(amount),
+            "s" => Duration::from_secs(amount),
+            "m" => Duration::from_secs(amount * 60),
+            "h" => Duration::from_secs(amount * 3600),
+            "d" => Duration::from_secs(amount * 86_400),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit..];
+    }
+    Ok(total)
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+fn parse_value(text: &str, now: NaiveDateTime) -> Option<TimeValue> {
+    let mut parts = text.split_whitespace();
+    let first = parts.next()?;
+    let Some(clock) = parts.next() else {
+        return parse_seconds(first);
+    };
+    let zone = parts.next();
+
+    // "2023-10-17" with -v year, otherwise "10-17" and the year is inferred
+    let date: Vec<&str> = first.split('-').collect();
+    let (year, month, day) = match date.as_slice() {
+        [year, month, day] => (Some(year.parse().ok()?), month, day),
+        [month, day] => (None, month, day),
+        _ => return None,
+    };
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+
+    let (hms, fraction) = clock.split_once('.')?;
+    let mut hms = hms.split(':').map(|n| n.parse::<u32>().ok());
+    let (hour, minute, second) = (hms.next()??, hms.next()??, hms.next()??);
+    let nanos = parse_fraction(fraction)?;
+
+    let at = |year: i32| {
+        NaiveDate::from_ymd_opt(year, month, day)?.and_hms_nano_opt(hour, minute, second, nanos)
+    };
+    let naive = match year {
+        Some(year) => at(year)?,
+        None => infer_year(at, now)?,
+    };
+
+    let time = match zone.and_then(parse_offset) {
+        Some(offset) => offset.from_local_datetime(&naive).single()?,
+        None => Local.from_local_datetime(&naive).earliest()?.fixed_offset(),
+    };
+    Some(TimeValue::Wall(time))
+}
+
+/// Logs carry no year: assume the current one, unless that puts the line in
+/// the future (a December log read in January), in which case it is last year's.
+fn infer_year(
+    at: impl Fn(i32) -> Option<NaiveDateTime>,
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    match at(now.year()) {
+        Some(time) if time <= now + chrono::Duration::days(1) => Some(time),
+        // Feb 29 only exists in leap years; take the latest one that has it
+        _ => (1..=4).find_map(|back| at(now.year() - back)),
+    }
+}
+
+fn parse_seconds(text: &str) -> Option<TimeValue> {
+    let (seconds, fraction) = text.split_once('.')?;
+    let seconds: u64 = seconds.parse().ok()?;
+    let nanos = parse_fraction(fraction)?;
+
+    if seconds < EPOCH_THRESHOLD {
+        return Some(TimeValue::Uptime(Duration::new(seconds, nanos)));
+    }
+    let time = DateTime::<Utc>::from_timestamp(seconds.try_into().ok()?, nanos)?;
+    Some(TimeValue::Wall(time.with_timezone(&Local).fixed_offset()))
+}
+
+/// Digits after the decimal point ("789", "789123", ...) as nanoseconds.
+fn parse_fraction(digits: &str) -> Option<u32> {
+    if digits.is_empty() || digits.len() > 9 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: u32 = digits.parse().ok()?;
+    Some(value * 10u32.pow(9 - digits.len() as u32))
+}
+
+/// "+0200" / "-0530" as printed by `-v zone`.
+fn parse_offset(text: &str) -> Option<FixedOffset> {
+    let (sign, digits) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn parse(text: &str) -> Timestamp {
+        Timestamp::parse(text, Local::now().naive_local())
+    }
+
+    fn wall(text: &str) -> DateTime<FixedOffset> {
+        match parse(text).value() {
+            Some(TimeValue::Wall(time)) => time,
+            other => panic!("{}: {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration(" 2d "), Ok(Duration::from_secs(2 * 86_400)));
+        for bad in ["", "5", "m", "5x", "1.5s", "-5s"] {
+            assert!(parse_duration(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn every_time_column_parses() {
+        let year = wall("2023-10-17 12:34:56.789");
+        assert_eq!((year.year(), year.month(), year.day()), (2023, 10, 17));
+        assert_eq!(year.nanosecond(), 789_000_000);
+
+        assert_eq!(wall("10-17 12:34:56.789123").nanosecond(), 789_123_000);
+        assert_eq!(wall("10-17 12:34:56.789123456").nanosecond(), 789_123_456);
+
+        let zoned = wall("2023-10-17 12:34:56.789 +0530");
+        assert_eq!(zoned.offset().local_minus_utc(), 5 * 3600 + 30 * 60);
+        assert_eq!(zoned.hour(), 12);
+
+        assert_eq!(wall("1697551330.500").timestamp(), 1_697_551_330);
+        assert_eq!(
+            parse("12345.678").value(),
+            Some(TimeValue::Uptime(Duration::new(12345, 678_000_000)))
+        );
+
+        for bad in [
+            "",
+            "12:34:56",
+            "10-17 12:34:56",
+            "13-40 12:34:56.789",
+            "1.5s",
+        ] {
+            assert_eq!(parse(bad).value(), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn explicit_years_order_across_new_year() {
+        let old = parse("2023-12-31 23:59:59.999");
+        let new = parse("2024-01-01 00:00:00.000");
+        assert_eq!(old.cmp_time(&new), Ordering::Less);
+        // Offsets are compared as instants, not as the digits printed
+        let east = parse("2024-01-01 00:30:00.000 +0100");
+        let utc = parse("2023-12-31 23:45:00.000 +0000");
+        assert_eq!(east.cmp_time(&utc), Ordering::Less);
+    }
+
+    #[test]
+    fn missing_year_never_lands_in_the_future() {
+        let year_of = |text: &str, now: &str| {
+            let now = NaiveDateTime::parse_from_str(now, "%Y-%m-%d %H:%M").unwrap();
+            match Timestamp::parse(text, now).value() {
+                Some(TimeValue::Wall(time)) => time.year(),
+                other => panic!("{}: {:?}", text, other),
+            }
+        };
+        // A December log read in January is last year's
+        assert_eq!(year_of("12-31 23:59:59.999", "2024-01-01 00:10"), 2023);
+        // Up to a day ahead is clock skew, not a year-old line
+        assert_eq!(year_of("01-01 12:00:00.000", "2024-01-01 00:10"), 2024);
+        assert_eq!(year_of("01-03 12:00:00.000", "2024-01-01 00:10"), 2023);
+        // Feb 29 goes back to the latest leap year
+        assert_eq!(year_of("02-29 12:00:00.000", "2025-03-01 00:00"), 2024);
+    }
+
+    #[test]
+    fn absolute_points() {
+        let line = parse("2023-10-17 14:02:10.500");
+        let at = |text: &str| TimePoint::parse(text).unwrap().cmp_timestamp(&line);
+
+        assert_eq!(at("14:02:10.500"), Some(Ordering::Equal));
+        assert_eq!(at("14:02"), Some(Ordering::Greater));
+        assert_eq!(at("15:00"), Some(Ordering::Less));
+        assert_eq!(at("2023-10-17 14:02:10"), Some(Ordering::Greater));
+        assert_eq!(at("2023-10-17 14:02:11"), Some(Ordering::Less));
+        assert_eq!(at("2023-10-18 00:00"), Some(Ordering::Less));
+        assert!(TimePoint::parse("noon").is_err());
+        assert!(TimePoint::parse("10-17").is_err());
+    }
+
+    #[test]
+    fn uptime_and_wall_clock_do_not_compare() {
+        let uptime = parse("120.000");
+        let wall = TimePoint::parse("2023-10-17 14:02:10").unwrap();
+        assert_eq!(wall.cmp_timestamp(&uptime), None);
+        assert_eq!(
+            TimePoint::parse("14:02").unwrap().cmp_timestamp(&uptime),
+            None
+        );
+        assert_eq!(wall.cmp_timestamp(&Timestamp::default()), None);
+
+        let boot = TimePoint::parse("100.0").unwrap();
+        assert_eq!(boot.cmp_timestamp(&uptime), Some(Ordering::Greater));
+    }
+
+    #[test]
+    fn relative_ranges_count_back_from_an_anchor() {
+        let anchor = parse("2024-01-01 00:00:10.000 +0000")
+            .value()
+            .unwrap();
+        let since = TimePoint::At(anchor.before(Duration::from_secs(30)));
+        let inside = parse("2023-12-31 23:59:50.000 +0000");
+        let outside = parse("2023-12-31 23:59:30.000 +0000");
+        assert_eq!(since.cmp_timestamp(&inside), Some(Ordering::Greater));
+        assert_eq!(since.cmp_timestamp(&outside), Some(Ordering::Less));
+
+        let uptime = TimeValue::Uptime(Duration::from_secs(10));
+        assert_eq!(
+            uptime.before(Duration::from_secs(30)),
+            TimeValue::Uptime(Duration::ZERO)
+        );
+    }
+}
@@ -1,29 +1,95 @@
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Style};
-use ratatui::text::Line;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 use ratatui::Frame;
 
+use crate::adb::Device;
 use crate::app::App;
+use crate::ui::log_view::device_color;
+
+/// Each device takes a serial/state row and a model/transport row.
+pub const ROWS_PER_DEVICE: usize = 2;
+
+fn state_color(device: &Device) -> Color {
+    match device.state.as_str() {
+        "device" => Color::Green,
+        "offline" => Color::Red,
+        _ => Color::Yellow,
+    }
+}
 
 pub fn render(f: &mut Frame, area: Rect, app: &App) {
-    let lines: Vec<Line> = if app.device_list.is_empty() {
+    let lines: Vec<Line> = if app.devices.is_empty() {
         vec![Line::styled(
             "No devices found",
             Style::default().fg(Color::Yellow),
         )]
     } else {
-        app.device_list
+        app.devices
             .iter()
-            .map(|d| Line::styled(d.clone(), Style::default().fg(Color::White)))
+            .enumerate()
+            .flat_map(|(i, device)| render_device(app, i, device))
             .collect()
     };
 
+    let title = if app.device_focus {
+        format!(" Devices [{}] m:merge f:only ", app.devices.len())
+    } else {
+        format!(" Devices [{}] (Tab) ", app.devices.len())
+    };
+    let border_color = if app.device_focus {
+        Color::Cyan
+    } else {
+        Color::Blue
+    };
     let block = Block::default()
         .borders(Borders::ALL)
-        .title(format!(" Devices [{}] ", app.device_list.len()))
-        .border_style(Style::default().fg(Color::Blue));
+        .title(title)
+        .border_style(Style::default().fg(border_color));
 
     let paragraph = Paragraph::new(lines).block(block);
     f.render_widget(paragraph, area);
 }
+
+fn render_device(app: &App, index: usize, device: &Device) -> [Line<'static>; ROWS_PER_DEVICE] {
+    let active = app.is_streaming_from(&device.serial);
+    let marker = if active { "▶" } else { " " };
+    let gutter = match app.device_color_index(Some(&device.serial)) {
+        Some(i) if app.seen_devices.len() > 1 => {
+            Span::styled("▌", Style::default().fg(device_color(i)))
+        }
+        _ => Span::raw(" "),
+    };
+    let serial_style = if active {
+        Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::White)
+    };
+
+    let mut details = device.model.clone().unwrap_or_default();
+    if let Some(ref id) = device.transport_id {
+        details.push_str(&format!(" (transport {})", id));
+    }
+
+    let mut header = Line::from(vec![
+        gutter,
+        Span::styled(format!("{}{} ", marker, device.serial), serial_style),
+        Span::styled(device.state.clone(), Style::default().fg(state_color(device))),
+    ]);
+    if app.filters.device_filter.as_deref() == Some(device.serial.as_str()) {
+        header.push_span(Span::styled(" [only]", Style::default().fg(Color::Cyan)));
+    }
+    let mut detail = Line::styled(
+        format!("    {}", details.trim_start()),
+        Style::default().fg(Color::DarkGray),
+    );
+
+    if app.device_focus && index == app.device_cursor {
+        let highlight = Style::default().bg(Color::Rgb(42, 76, 132));
+        header = header.patch_style(highlight);
+        detail = detail.patch_style(highlight);
+    }
+
+    [header, detail]
+}
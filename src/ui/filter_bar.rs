@@ -1,21 +1,25 @@
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
 
 use crate::app::{App, InputMode};
 use crate::parser::LogLevel;
 
-const LEVELS: [LogLevel; 6] = [
+const LEVELS: [LogLevel; 7] = [
     LogLevel::Verbose,
     LogLevel::Debug,
     LogLevel::Info,
     LogLevel::Warn,
     LogLevel::Error,
     LogLevel::Fatal,
+    LogLevel::Unknown,
 ];
 
+const TAG_PROMPT: &str = " tag:";
+const PACKAGE_PROMPT: &str = " pkg:";
+
 fn level_color(level: LogLevel) -> Color {
     match level {
         LogLevel::Verbose => Color::DarkGray,
@@ -24,7 +28,36 @@ fn level_color(level: LogLevel) -> Color {
         LogLevel::Warn => Color::Yellow,
         LogLevel::Error => Color::Red,
         LogLevel::Fatal => Color::Magenta,
+        LogLevel::Unknown => Color::DarkGray,
+    }
+}
+
+/// Prompt text turns red while it would be rejected.
+fn input_style(app: &App) -> Style {
+    let color = if app.filter_error.is_some() {
+        Color::Red
+    } else {
+        Color::White
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+/// The prompt text with a block cursor at the editing position.
+fn input_spans(app: &App, cursor_color: Color) -> Vec<Span<'static>> {
+    let text: &str = &app.filter_input;
+    let (before, rest) = text.split_at(app.filter_input.cursor());
+    let mut chars = rest.chars();
+    let under = chars.next();
+    let mut spans = vec![Span::styled(before.to_string(), input_style(app))];
+    match under {
+        Some(c) => spans.push(Span::styled(
+            c.to_string(),
+            input_style(app).fg(Color::Black).bg(cursor_color),
+        )),
+        None => spans.push(Span::styled("█", Style::default().fg(cursor_color))),
     }
+    spans.push(Span::styled(chars.as_str().to_string(), input_style(app)));
+    spans
 }
 
 pub fn render(f: &mut Frame, area: Rect, app: &App) {
@@ -34,51 +67,155 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
     match app.input_mode {
         InputMode::Filter => {
             spans.push(Span::styled(" /", Style::default().fg(Color::Yellow)));
-            spans.push(Span::styled(
-                app.filter_input.clone(),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
-            ));
-            spans.push(Span::styled("█", Style::default().fg(Color::Yellow)));
+            spans.extend(input_spans(app, Color::Yellow));
+            if let Some(ref error) = app.filter_error {
+                spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::default().fg(Color::Red),
+                ));
+            }
         }
         InputMode::Tag => {
-            spans.push(Span::styled(" tag:", Style::default().fg(Color::Cyan)));
+            spans.push(Span::styled(TAG_PROMPT, Style::default().fg(Color::Cyan)));
+            spans.extend(input_spans(app, Color::Cyan));
+            if let Some(ref error) = app.filter_error {
+                spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+        }
+        InputMode::Search => {
+            spans.push(Span::styled(" find:", Style::default().fg(Color::LightYellow)));
+            spans.extend(input_spans(app, Color::LightYellow));
+            match app.filter_error {
+                Some(ref error) => spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::default().fg(Color::Red),
+                )),
+                None => spans.push(Span::styled(
+                    "  (regex; n/N jump, lines are not hidden)",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            }
+        }
+        InputMode::Goto => {
+            spans.push(Span::styled(" goto:", Style::default().fg(Color::LightBlue)));
+            spans.extend(input_spans(app, Color::LightBlue));
+            match app.filter_error {
+                Some(ref error) => spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::default().fg(Color::Red),
+                )),
+                None => spans.push(Span::styled(
+                    "  (14:02:10, 10-17 14:02:10, or -30s before the newest line)",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            }
+        }
+        InputMode::Exclude => {
+            spans.push(Span::styled(" exclude:", Style::default().fg(Color::LightRed)));
+            spans.extend(input_spans(app, Color::LightRed));
+            match app.filter_error {
+                Some(ref error) => spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::default().fg(Color::Red),
+                )),
+                None => spans.push(Span::styled(
+                    "  (tags, ~regex for messages)",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            }
+        }
+        InputMode::Package => {
+            spans.push(Span::styled(PACKAGE_PROMPT, Style::default().fg(Color::Green)));
+            spans.extend(input_spans(app, Color::Green));
+            if let Some(ref error) = app.filter_error {
+                spans.push(Span::styled(
+                    format!("  {}", error),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+        }
+        InputMode::Buffers => {
+            spans.push(Span::styled(" buffers:", Style::default().fg(Color::Blue)));
+            spans.extend(input_spans(app, Color::Blue));
             spans.push(Span::styled(
-                app.filter_input.clone(),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                "  (main,system,crash,events,radio,kernel | all | empty = default)",
+                Style::default().fg(Color::DarkGray),
             ));
-            spans.push(Span::styled("█", Style::default().fg(Color::Cyan)));
         }
-        InputMode::Package => {
-            spans.push(Span::styled(" pkg:", Style::default().fg(Color::Green)));
+        InputMode::PresetName => {
+            spans.push(Span::styled(" preset:", Style::default().fg(Color::LightMagenta)));
+            spans.extend(input_spans(app, Color::LightMagenta));
             spans.push(Span::styled(
-                app.filter_input.clone(),
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                "  (name for the current filters; an existing name is replaced)",
+                Style::default().fg(Color::DarkGray),
             ));
-            spans.push(Span::styled("█", Style::default().fg(Color::Green)));
         }
         InputMode::Normal => {
             // Show active filter if any
-            if let Some(ref re) = app.filters.regex_filter {
+            if let Some(ref query) = app.filters.query {
                 spans.push(Span::styled(
-                    format!(" /{}/", re.as_str()),
+                    format!(" /{}/", query.source),
                     Style::default().fg(Color::Yellow),
                 ));
             }
-            if let Some(ref tag) = app.filters.tag_filter {
+            if let Some(tag) = app.filters.tag_filter() {
                 spans.push(Span::styled(
                     format!(" tag:{}", tag),
                     Style::default().fg(Color::Cyan),
                 ));
             }
+            if app.context > 0 && app.filters.has_pattern() {
+                spans.push(Span::styled(
+                    format!(" ±{}", app.context),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            if !app.filters.exclude_tags.is_empty() || !app.filters.exclude_patterns.is_empty() {
+                spans.push(Span::styled(
+                    format!(" exclude:{}", app.filters.exclude_text()),
+                    Style::default().fg(Color::LightRed),
+                ));
+            }
+            if let Some(ref re) = app.search {
+                spans.push(Span::styled(
+                    format!(" find:{}", re.as_str()),
+                    Style::default().fg(Color::LightYellow),
+                ));
+            }
+            if let Some(ref device) = app.filters.device_filter {
+                spans.push(Span::styled(
+                    format!(" dev:{}", device),
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
             if let Some(ref pkg) = app.package_filter {
-                let pid_str = app.filters.pid_filter
-                    .map(|p| format!(" (pid:{})", p))
-                    .unwrap_or_else(|| " (resolving...)".to_string());
+                let pids: Vec<String> = app.filters.package_pids().map(|pid| pid.to_string()).collect();
+                let pid_str = if pids.is_empty() {
+                    " (resolving...)".to_string()
+                } else {
+                    format!(" (pid:{})", pids.join(","))
+                };
                 spans.push(Span::styled(
                     format!(" pkg:{}{}", pkg, pid_str),
                     Style::default().fg(Color::Green),
                 ));
             }
+            if let Some((_, tid)) = &app.filters.thread_filter {
+                spans.push(Span::styled(
+                    format!(" thread:{}", tid),
+                    Style::default().fg(Color::Blue),
+                ));
+            }
+            if !app.filters.uid_filter.is_empty() {
+                let uids: Vec<String> = app.filters.uid_filter.iter().map(u32::to_string).collect();
+                spans.push(Span::styled(
+                    format!(" uid:{}", uids.join(",")),
+                    Style::default().fg(Color::Green),
+                ));
+            }
             if spans.is_empty() {
                 spans.push(Span::styled(
                     " No active filters",
@@ -88,6 +225,31 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
         }
     }
 
+    // Match options, shown while they matter: in the pattern prompts or with
+    // a pattern filter active
+    let patterns_shown = match app.input_mode {
+        InputMode::Filter | InputMode::Tag => true,
+        InputMode::Normal => app.filters.has_pattern(),
+        _ => false,
+    };
+    if patterns_shown {
+        let options = app.filters.match_options;
+        spans.push(Span::raw(" "));
+        for (label, on) in [
+            ("Aa", options.smart_case),
+            (".*", !options.literal),
+            ("\\b", options.whole_word),
+        ] {
+            let style = if on {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            spans.push(Span::styled(label, style));
+            spans.push(Span::raw(" "));
+        }
+    }
+
     // Separator
     spans.push(Span::styled("  │ ", Style::default().fg(Color::DarkGray)));
 
@@ -118,3 +280,47 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
     let paragraph = Paragraph::new(line).block(block);
     f.render_widget(paragraph, area);
 }
+
+/// Suggestions for the tag and package prompts, dropped down over the logs
+/// from under the prompt text.
+pub fn render_completions(f: &mut Frame, area: Rect, app: &App) {
+    let prompt = match app.input_mode {
+        InputMode::Tag => TAG_PROMPT,
+        InputMode::Package => PACKAGE_PROMPT,
+        _ => return,
+    };
+    if app.completions.is_empty() {
+        return;
+    }
+    let x = area.x + prompt.len() as u16 - 1;
+    let width = app
+        .completions
+        .iter()
+        .map(|c| c.chars().count() as u16 + 2)
+        .max()
+        .unwrap_or(0)
+        .max(20)
+        .min(area.right().saturating_sub(x));
+    let height = (app.completions.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let popup = Rect::new(x, area.y + 1, width, height);
+
+    let lines: Vec<Line> = app
+        .completions
+        .iter()
+        .enumerate()
+        .map(|(i, completion)| {
+            let style = if app.completion_selected == Some(i) {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::from(Span::styled(completion.clone(), style))
+        })
+        .collect();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Tab ")
+        .border_style(Style::default().fg(Color::DarkGray));
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(lines).block(block), popup);
+}
@@ -13,12 +13,31 @@ const HELP_LINES: &[(&str, &str)] = &[
     ("h / ?", "Toggle help"),
     ("", ""),
     ("--- Filtering ---", ""),
-    ("/", "Regex filter"),
-    ("t", "Tag filter"),
-    ("p", "Package filter (auto PID lookup)"),
+    ("/", "Query filter (level>=W tag:A|B -tag:C msg~re pid:N)"),
+    ("", "                after:14:02 before:14:03 last:30s"),
+    ("f", "Search (highlight, keep context)"),
+    ("n / N", "Next / previous match"),
+    ("g", "Go to time (14:02:10, -30s)"),
+    ("t", "Tag filter (text, or ~regex)"),
+    ("Alt+C/R/W", "Smart case / literal / whole word"),
+    ("[ / ]", "Less / more context around matches"),
+    ("T", "Exclude tags / ~regex (saved)"),
+    ("m / i", "Mute / isolate tag under mouse"),
+    ("u", "Unmute last muted tag"),
+    ("I / mid click", "Isolate thread under mouse"),
+    ("p", "Package filter (follows restarts)"),
+    ("U", "Only the package's uid (toggle)"),
+    ("F", "Filter presets (s save, e share)"),
     ("1-6", "Toggle V/D/I/W/E/F levels"),
+    ("7", "Toggle unparsed lines"),
     ("Esc", "Clear filter / cancel input"),
     ("", ""),
+    ("--- Prompts ---", ""),
+    ("←/→ Home/End", "Move cursor (Ctrl/Alt+arrow: by word)"),
+    ("Ctrl+W/U/K", "Delete word / to start / to end"),
+    ("↑/↓", "Prompt history (saved between runs)"),
+    ("Tab / Shift+Tab", "Complete tag / package"),
+    ("", ""),
     ("--- Navigation ---", ""),
     ("Space", "Pause / resume tailing"),
     ("↑/↓ or j/k", "Scroll line-by-line"),
@@ -32,8 +51,15 @@ const HELP_LINES: &[(&str, &str)] = &[
     ("--- Panels/Actions ---", ""),
     ("x", "Toggle crash/ANR panel"),
     ("d", "Toggle device panel"),
+    ("Tab", "Focus device panel"),
+    ("Enter / click", "Stream from device"),
+    ("m (devices)", "Merge device into view"),
+    ("f (devices)", "Show only that device"),
     ("y", "Copy selected log lines"),
+    ("e / E", "Expand selected / all stack traces"),
+    ("P", "Cycle process / thread name columns"),
     ("s", "Save visible logs"),
+    ("b", "Choose logcat buffers"),
     ("c", "Clear logcat buffer"),
 ];
 
@@ -95,7 +121,7 @@ fn build_lines() -> Vec<Line<'static>> {
         .collect()
 }
 
-fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let vertical = Layout::vertical([Constraint::Length(height)])
         .flex(Flex::Center)
         .split(area);
@@ -3,10 +3,11 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
 use ratatui::Frame;
+use regex::Regex;
 
-use crate::app::App;
+use crate::app::{App, NameColumns};
 use crate::json;
-use crate::parser::LogLevel;
+use crate::parser::{EntryKind, LogBuffer, LogLevel};
 
 fn level_color(level: LogLevel) -> Color {
     match level {
@@ -16,9 +17,38 @@ fn level_color(level: LogLevel) -> Color {
         LogLevel::Warn => Color::Yellow,
         LogLevel::Error => Color::Red,
         LogLevel::Fatal => Color::Magenta,
+        LogLevel::Unknown => Color::DarkGray,
     }
 }
 
+const DEVICE_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::LightRed,
+];
+
+pub fn device_color(index: usize) -> Color {
+    DEVICE_COLORS[index % DEVICE_COLORS.len()]
+}
+
+/// Width of the process name column; longer names keep their end, where
+/// package names differ (`…example.app:remote`).
+const PROCESS_WIDTH: usize = 24;
+/// Thread names (`comm`) are at most 15 characters.
+const THREAD_WIDTH: usize = 15;
+
+fn truncate_start(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    if len <= width {
+        return text.to_string();
+    }
+    let tail: String = text.chars().skip(len - width + 1).collect();
+    format!("…{}", tail)
+}
+
 fn level_style(level: LogLevel) -> Style {
     let color = level_color(level);
     let style = Style::default().fg(color);
@@ -32,20 +62,23 @@ fn level_style(level: LogLevel) -> Style {
 pub fn render(f: &mut Frame, area: Rect, app: &App) {
     let height = area.height.saturating_sub(2) as usize; // block borders
     let total = app.filtered_indices.len();
-    let (start, end) = app.visible_bounds(height);
+    let (start, end, skip) = app.visible_window(height);
 
     let lines: Vec<Line> = app.filtered_indices[start..end]
         .iter()
-        .filter_map(|&idx| {
-            app.entry_at(idx)
-                .map(|entry| render_entry(entry, app.selection_contains(idx)))
-        })
+        .flat_map(|&idx| render_group(app, idx))
+        .skip(skip)
         .collect();
 
+    let buffers = if app.buffers.is_empty() {
+        String::new()
+    } else {
+        format!(" | {}", LogBuffer::list_to_string(&app.buffers))
+    };
     let title = if app.tailing {
-        format!(" Logs [{} | TAILING] ", total)
+        format!(" Logs [{} | TAILING{}] ", total, buffers)
     } else {
-        format!(" Logs [{}/{} | PAUSED] ", end, total)
+        format!(" Logs [{}/{} | PAUSED{}] ", end, total, buffers)
     };
 
     let block = Block::default()
@@ -57,42 +90,178 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(paragraph, area);
 }
 
-fn render_entry(entry: &crate::parser::LogEntry, selected: bool) -> Line<'static> {
+/// An entry's row, followed by its folded lines when expanded.
+fn render_group(app: &App, idx: usize) -> Vec<Line<'static>> {
+    let Some(entry) = app.entry_at(idx) else {
+        return Vec::new();
+    };
+    let selected = app.selection_contains(idx);
+    let current = app.search_current == Some(idx);
+    let mut lines = vec![render_entry(app, entry, selected, current)];
+    if app.is_expanded(idx) {
+        lines.extend(
+            entry
+                .folded
+                .iter()
+                .map(|line| render_entry(app, line, selected, current)),
+        );
+    }
+    // Context around filter matches is dimmed, hunks split by "--" like grep -C
+    if app.is_context(idx) {
+        lines = lines
+            .into_iter()
+            .map(|line| line.patch_style(Style::default().add_modifier(Modifier::DIM)))
+            .collect();
+    }
+    if app.starts_hunk(idx) {
+        lines.insert(0, Line::styled("--", Style::default().fg(Color::DarkGray)));
+    }
+    lines
+}
+
+fn render_entry(
+    app: &App,
+    entry: &crate::parser::LogEntry,
+    selected: bool,
+    current_match: bool,
+) -> Line<'static> {
+    // Per-device gutter when several devices are merged into one view
+    let mut spans = Vec::new();
+    if app.seen_devices.len() > 1 {
+        let gutter = match app.device_color_index(entry.device.as_deref()) {
+            Some(i) => Span::styled("▌", Style::default().fg(device_color(i))),
+            None => Span::raw(" "),
+        };
+        spans.push(gutter);
+    }
+
+    if entry.kind == EntryKind::Marker {
+        spans.push(Span::styled(
+            format!("{} {}", entry.timestamp, entry.raw),
+            Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        ));
+        return highlight_selected(Line::from(spans), selected);
+    }
+
+    // Lines no format matched are shown verbatim, dimmed
+    if matches!(entry.kind, EntryKind::Unparsed | EntryKind::Continuation) {
+        spans.push(Span::styled(
+            entry.raw.clone(),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM),
+        ));
+        return highlight_selected(Line::from(spans), selected);
+    }
+
     let color = level_color(entry.level);
     let lstyle = level_style(entry.level);
 
-    let mut spans = vec![
-        Span::styled(
+    // Formats like brief or tag carry no time/thread columns; don't invent them
+    if entry.format.has_timestamp() {
+        spans.push(Span::styled(
             format!("{} ", entry.timestamp),
             Style::default().fg(Color::DarkGray),
-        ),
-        Span::styled(
-            format!("{:>5} {:>5} ", entry.pid, entry.tid),
-            Style::default().fg(Color::DarkGray),
-        ),
-        Span::styled(format!("{} ", entry.level), lstyle),
-        Span::styled(
-            format!("{}: ", entry.tag),
-            Style::default().fg(Color::DarkGray),
-        ),
-    ];
+        ));
+    }
+    let mut ids = match (entry.format.has_pid(), entry.format.has_tid()) {
+        (true, true) => format!("{:>5} {:>5} ", entry.pid, entry.tid),
+        (true, false) => format!("{:>5}       ", entry.pid),
+        _ => String::new(),
+    };
+    if let Some(uid) = entry.uid {
+        ids.insert_str(0, &format!("{:>7} ", uid));
+    }
+    spans.push(Span::styled(ids, Style::default().fg(Color::DarkGray)));
+    if app.name_columns != NameColumns::Off && entry.format.has_pid() {
+        let name = app.processes.name(entry).unwrap_or("");
+        spans.push(Span::styled(
+            format!("{:<width$} ", truncate_start(name, PROCESS_WIDTH), width = PROCESS_WIDTH),
+            Style::default().fg(Color::Blue),
+        ));
+    }
+    if app.name_columns == NameColumns::ProcessAndThread && entry.format.has_tid() {
+        let name = app.processes.thread_name(entry).unwrap_or("");
+        let style = if name == "main" {
+            Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Blue)
+        };
+        spans.push(Span::styled(
+            format!("{:<width$} ", truncate_start(name, THREAD_WIDTH), width = THREAD_WIDTH),
+            style,
+        ));
+    }
+    spans.push(Span::styled(format!("{} ", entry.level), lstyle));
+    let search = app.search.as_ref().map(|re| (re, match_style(current_match)));
+    if entry.format.has_tag() {
+        let style = Style::default().fg(Color::DarkGray);
+        spans.extend(highlight_matches(&entry.tag, style, search));
+        spans.push(Span::styled(": ", style));
+    }
 
     // Check for JSON in message
     if let Some(pretty_json) = entry.pretty_json.as_deref() {
         spans.extend(json::colorize_json(pretty_json));
     } else {
-        spans.push(Span::styled(entry.message.clone(), Style::default().fg(color)));
+        spans.extend(highlight_matches(
+            &entry.message,
+            Style::default().fg(color),
+            search,
+        ));
+    }
+
+    if !entry.folded.is_empty() {
+        spans.push(Span::styled(
+            format!("  [+{} lines]", entry.folded.len()),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
 
-    let mut line = Line::from(spans);
+    highlight_selected(Line::from(spans), selected)
+}
+
+fn match_style(current: bool) -> Style {
+    let bg = if current {
+        Color::LightRed
+    } else {
+        Color::Yellow
+    };
+    Style::default().fg(Color::Black).bg(bg)
+}
+
+/// Split `text` into spans, with search matches in `search`'s style.
+fn highlight_matches(
+    text: &str,
+    style: Style,
+    search: Option<(&Regex, Style)>,
+) -> Vec<Span<'static>> {
+    let Some((re, match_style)) = search else {
+        return vec![Span::styled(text.to_string(), style)];
+    };
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for m in re.find_iter(text).filter(|m| !m.is_empty()) {
+        if m.start() > last {
+            spans.push(Span::styled(text[last..m.start()].to_string(), style));
+        }
+        spans.push(Span::styled(m.as_str().to_string(), match_style));
+        last = m.end();
+    }
+    if last < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[last..].to_string(), style));
+    }
+    spans
+}
+
+fn highlight_selected(line: Line<'static>, selected: bool) -> Line<'static> {
     if selected {
-        line = line.patch_style(
+        line.patch_style(
             Style::default()
                 .bg(Color::Rgb(42, 76, 132))
                 .add_modifier(Modifier::BOLD),
-        );
+        )
+    } else {
+        line
     }
-    line
 }
 
 pub fn render_crash_panel(f: &mut Frame, area: Rect, app: &App) {
@@ -102,7 +271,7 @@ pub fn render_crash_panel(f: &mut Frame, area: Rect, app: &App) {
 
     let lines: Vec<Line> = app.crash_indices[start..]
         .iter()
-        .filter_map(|&idx| app.entry_at(idx).map(|entry| render_entry(entry, false)))
+        .filter_map(|&idx| app.entry_at(idx).map(|entry| render_entry(app, entry, false, false)))
         .collect();
 
     let title = format!(" Crashes/ANRs [{}] ", total);
@@ -3,6 +3,7 @@ pub mod device_panel;
 pub mod filter_bar;
 pub mod help;
 pub mod log_view;
+pub mod preset_picker;
 pub mod status_bar;
 
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
@@ -44,9 +45,23 @@ pub fn render(f: &mut Frame, app: &App) {
     } else {
         render_content(f, content_area, app);
     }
+
+    if app.show_presets {
+        preset_picker::render(f, content_area, app);
+    }
+    filter_bar::render_completions(f, size, app);
 }
 
 pub fn log_view_area(size: Rect, app: &App) -> Rect {
+    split_content(content_area(size, app), app).0
+}
+
+/// Area of the crash/device side panel, if one is open.
+pub fn side_panel_area(size: Rect, app: &App) -> Option<Rect> {
+    split_content(content_area(size, app), app).1
+}
+
+fn content_area(size: Rect, app: &App) -> Rect {
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -57,51 +72,39 @@ pub fn log_view_area(size: Rect, app: &App) -> Rect {
         .split(size);
 
     let content_area = main_chunks[1];
-    let content_area = if app.show_help && content_area.width >= 90 {
+    if app.show_help && content_area.width >= 90 {
         Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Min(30), Constraint::Length(44)])
             .split(content_area)[0]
     } else {
         content_area
-    };
-
-    match app.panels {
-        PanelLayout::Single => content_area,
-        PanelLayout::SplitCrash => Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-            .split(content_area)[0],
-        PanelLayout::SplitDevice => Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Min(40), Constraint::Length(35)])
-            .split(content_area)[0],
     }
 }
 
+fn split_content(area: Rect, app: &App) -> (Rect, Option<Rect>) {
+    let constraints = match app.panels {
+        PanelLayout::Single => return (area, None),
+        PanelLayout::SplitCrash => [Constraint::Percentage(60), Constraint::Percentage(40)],
+        PanelLayout::SplitDevice => [Constraint::Min(40), Constraint::Length(35)],
+    };
+    let h_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(area);
+    (h_chunks[0], Some(h_chunks[1]))
+}
+
 fn render_content(f: &mut Frame, area: Rect, app: &App) {
     // Content area depends on panel layout
-    match app.panels {
-        PanelLayout::Single => {
-            log_view::render(f, area, app);
-        }
-        PanelLayout::SplitCrash => {
-            let h_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-                .split(area);
-
-            log_view::render(f, h_chunks[0], app);
-            crash_panel::render(f, h_chunks[1], app);
-        }
-        PanelLayout::SplitDevice => {
-            let h_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Min(40), Constraint::Length(35)])
-                .split(area);
+    let (log_area, side_area) = split_content(area, app);
+    log_view::render(f, log_area, app);
 
-            log_view::render(f, h_chunks[0], app);
-            device_panel::render(f, h_chunks[1], app);
+    if let Some(side_area) = side_area {
+        match app.panels {
+            PanelLayout::SplitCrash => crash_panel::render(f, side_area, app),
+            PanelLayout::SplitDevice => device_panel::render(f, side_area, app),
+            PanelLayout::Single => {}
         }
     }
 }
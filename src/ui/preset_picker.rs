@@ -0,0 +1,55 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::preset::PROJECT_FILE;
+use crate::ui::help::centered_rect;
+
+pub fn render(f: &mut Frame, area: Rect, app: &App) {
+    let mut lines: Vec<Line> = if app.presets.is_empty() {
+        vec![Line::styled(
+            "No presets yet; press s to save the current filters",
+            Style::default().fg(Color::Yellow),
+        )]
+    } else {
+        app.presets
+            .iter()
+            .enumerate()
+            .map(|(i, preset)| {
+                let mut line = Line::from(vec![
+                    Span::styled(
+                        format!(" {:<18} ", preset.name),
+                        Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(preset.summary(), Style::default().fg(Color::Gray)),
+                ]);
+                if preset.shared {
+                    line.push_span(Span::styled(" [project]", Style::default().fg(Color::Cyan)));
+                }
+                if i == app.preset_cursor {
+                    line = line.patch_style(Style::default().bg(Color::Rgb(42, 76, 132)));
+                }
+                line
+            })
+            .collect()
+    };
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(
+        format!("Enter apply  s save current  d delete  e share via {}", PROJECT_FILE),
+        Style::default().fg(Color::DarkGray),
+    ));
+
+    let width = 80u16.min(area.width.saturating_sub(4));
+    let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let popup_area = centered_rect(width, height, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Presets [{}] (F) ", app.presets.len()))
+        .border_style(Style::default().fg(Color::LightMagenta));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(Paragraph::new(lines).block(block), popup_area);
+}
@@ -5,6 +5,7 @@ use ratatui::widgets::Paragraph;
 use ratatui::Frame;
 
 use crate::app::App;
+use crate::parser::LogLevel;
 
 pub fn render(f: &mut Frame, area: Rect, app: &App) {
     let mut spans: Vec<Span> = Vec::new();
@@ -34,6 +35,14 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
         ));
     }
 
+    let unparsed = app.stats.counts[LogLevel::Unknown.index()];
+    if unparsed > 0 {
+        spans.push(Span::styled(
+            format!(" │ {} unparsed", unparsed),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+
     if crashes > 0 {
         spans.push(Span::styled(
             format!(" │ {} crashes", crashes),
@@ -41,6 +50,24 @@ pub fn render(f: &mut Frame, area: Rect, app: &App) {
         ));
     }
 
+    if let Some((current, total)) = app.search_position() {
+        let text = match current {
+            Some(current) => format!(" │ find {}/{}", current, total),
+            None => format!(" │ find {} matches", total),
+        };
+        spans.push(Span::styled(
+            text,
+            Style::default().fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if app.is_waiting_for_device() {
+        spans.push(Span::styled(
+            " │ WAITING FOR DEVICE",
+            Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        ));
+    }
+
     // Tailing/Paused state
     if !app.tailing {
         spans.push(Span::styled(